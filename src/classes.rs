@@ -0,0 +1,54 @@
+use std::iter::Peekable;
+
+use crate::error::ParseError;
+use crate::leases::expect_semicolon;
+use crate::leases::parse_raw_statement;
+use crate::lex::LexItem;
+
+/// Class declaration written to the leases file when a class is created
+/// through OMAPI (`class "name" { dynamic; ... }`). Subclasses
+/// (`subclass "name" data { ... }`) carry their data in `subclass`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Class {
+    pub name: String,
+    pub subclass: Option<String>,
+    pub dynamic: bool,
+    /// Statements of the declaration, kept as raw text
+    pub statements: Vec<String>,
+}
+
+impl Class {
+    pub fn new<S: Into<String>>(name: S) -> Class {
+        Class {
+            name: name.into(),
+            subclass: None,
+            dynamic: false,
+            statements: Vec::new(),
+        }
+    }
+}
+
+pub fn parse_class<'l, T: Iterator<Item = &'l LexItem>>(
+    class: &mut Class,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        match nc {
+            LexItem::Word(w) if w == "dynamic" => {
+                class.dynamic = true;
+                iter.next();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Endl => (),
+            LexItem::Paren('}') => {
+                return Ok(());
+            }
+            _ => {
+                class.statements.push(parse_raw_statement(iter)?);
+            }
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
-use std::fmt;
 use std::cmp;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ParseError;
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+/// A UTC date and time. Dates compare and order by their calendar fields
+/// only, the weekday is informational.
+#[derive(Clone, Debug, Copy)]
 pub struct Date {
     pub weekday: i64,
     pub year: i64,
@@ -13,66 +18,179 @@ pub struct Date {
 }
 
 impl Date {
-    pub fn from<S: Into<String>>(wd: S, d: S, t: S) -> Result<Date, String> {
-        let weekday = wd.into();
-        let date = d.into();
-        let time = t.into();
+    pub fn from<S: Into<String>>(wd: S, d: S, t: S) -> Result<Date, ParseError> {
+        Date::from_dhcpd(wd.into(), d.into(), t.into()).map_err(ParseError::invalid_date)
+    }
 
+    fn from_dhcpd(weekday: String, date: String, time: String) -> Result<Date, String> {
         // Parses from `weekday year/month/day hour:minute:second` format as
         // specified in OpenBSD man page
         let mut result = Date::new();
-        result.weekday = weekday.parse::<i64>().expect("Error parsing weekday");
+        result.weekday = weekday
+            .parse::<i64>()
+            .map_err(|_| format!("Invalid weekday {:?}", weekday))?;
         if result.weekday < 0 || result.weekday > 6 {
-            return Err(format!("Weekday should be a number between 0 and 6. {} is not", weekday));
+            return Err(format!(
+                "Weekday should be a number between 0 and 6. {} is not",
+                weekday
+            ));
         }
 
         let d: Vec<&str> = date.split('/').collect();
         if d.len() != 3 {
-            return Err(format!("{} does not have expected date format (YYYY/MM/DD)", date));
+            return Err(format!(
+                "{} does not have expected date format (YYYY/MM/DD)",
+                date
+            ));
+        }
+        result.year = d[0]
+            .parse::<i64>()
+            .map_err(|_| format!("Year should be a number. {} is not", d[0]))?;
+        if result.year < 0 || result.year > 9999 {
+            return Err(format!(
+                "Year should be a number between 0 and 9999. {} is not",
+                result.year
+            ));
         }
-        result.year = d[0].to_string().parse::<i64>().expect("Year should be a number");
-        result.month = d[1].to_string().parse::<i64>().expect("Month should be a number");
-        if result.month < 1 {
-            return Err(format!("Month should be a number >= 1. {} is not", result.month));
+        result.month = d[1]
+            .parse::<i64>()
+            .map_err(|_| format!("Month should be a number. {} is not", d[1]))?;
+        if result.month < 1 || result.month > 12 {
+            return Err(format!(
+                "Month should be a number between 1 and 12. {} is not",
+                result.month
+            ));
         }
-        result.day = d[2].to_string().parse::<i64>().expect("Day should be a number");
-        if result.day < 1 {
-            return Err(format!("Day should be a number between >= 1. {} is not", result.day));
+        result.day = d[2]
+            .parse::<i64>()
+            .map_err(|_| format!("Day should be a number. {} is not", d[2]))?;
+        if result.day < 1 || result.day > 31 {
+            return Err(format!(
+                "Day should be a number between 1 and 31. {} is not",
+                result.day
+            ));
         }
 
         let t: Vec<&str> = time.split(':').collect();
         if t.len() != 3 {
-            return Err(format!("{} does not have expected time format (HH:mm:ss)", time));
+            return Err(format!(
+                "{} does not have expected time format (HH:mm:ss)",
+                time
+            ));
         }
-        result.hour = t[0].to_string().parse::<i64>().expect("Hour should be a number");
+        result.hour = t[0]
+            .parse::<i64>()
+            .map_err(|_| format!("Hour should be a number. {} is not", t[0]))?;
         if result.hour < 0 || result.hour > 23 {
-            return Err(format!("Hour should be a number between 0 and 23. {} is not", result.hour));
+            return Err(format!(
+                "Hour should be a number between 0 and 23. {} is not",
+                result.hour
+            ));
         }
-        result.minute = t[1].to_string().parse::<i64>().expect("Minute should be a number");
-        if result.minute < 0 || result.hour > 59 {
-            return Err(format!("Minute should be a number between 0 and 59. {} is not", result.minute));
+        result.minute = t[1]
+            .parse::<i64>()
+            .map_err(|_| format!("Minute should be a number. {} is not", t[1]))?;
+        if result.minute < 0 || result.minute > 59 {
+            return Err(format!(
+                "Minute should be a number between 0 and 59. {} is not",
+                result.minute
+            ));
         }
-        result.second = t[2].to_string().parse::<i64>().expect("Second should be a number");
-        if result.hour < 0 || result.hour > 59 {
-            return Err(format!("Second should be a number between 0 and 59. {} is not", result.second));
+        result.second = t[2]
+            .parse::<i64>()
+            .map_err(|_| format!("Second should be a number. {} is not", t[2]))?;
+        if result.second < 0 || result.second > 59 {
+            return Err(format!(
+                "Second should be a number between 0 and 59. {} is not",
+                result.second
+            ));
         }
 
         Ok(result)
     }
 
     /// Transforms UTC datetime in RFC3339 format into `Date` object
-    pub fn from_rfc3339<S: AsRef<str>>(weekday: u8, input: S) -> Result<Date, String> {
+    pub fn from_rfc3339<S: AsRef<str>>(weekday: u8, input: S) -> Result<Date, ParseError> {
         let input_s = input.as_ref();
         let parts: Vec<&str> = input_s.split('T').collect();
+        let invalid = || {
+            ParseError::invalid_date(format!(
+                "This doesn't seem like a correct RFC3339 date: {:?}",
+                input_s
+            ))
+        };
 
-        if parts.len() != 2 || parts[1].len() < 8 {
-            return Err(format!("This doesn't seem like a correct RFC3339 date: {:?}", input_s));
+        if parts.len() != 2 {
+            return Err(invalid());
         }
 
         let date = parts[0].replace("-", "/");
-        let time = parts[1].split_at(8).0.to_string();
+        let time = parts[1].get(..8).ok_or_else(invalid)?;
+
+        Date::from(weekday.to_string(), date, time.to_owned())
+    }
+
+    /// Transforms a number of seconds since the Unix epoch into a UTC `Date`
+    pub fn from_unix_timestamp(timestamp: i64) -> Date {
+        let days = timestamp.div_euclid(86400);
+        let secs = timestamp.rem_euclid(86400);
+
+        // civil calendar from days, with eras of 400 years starting in March
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Date {
+            // 1970/01/01 was a Thursday
+            weekday: (days + 4).rem_euclid(7),
+            year,
+            month,
+            day,
+            hour: secs / 3600,
+            minute: secs % 3600 / 60,
+            second: secs % 60,
+        }
+    }
+
+    /// Number of seconds since the Unix epoch, the inverse of
+    /// `from_unix_timestamp`. The weekday is ignored. Returns `None` when the
+    /// date is too far away to be represented.
+    pub fn to_unix_timestamp(&self) -> Option<i64> {
+        // days from civil calendar, with eras of 400 years starting in March
+        let year = if self.month <= 2 {
+            self.year.checked_sub(1)?
+        } else {
+            self.year
+        };
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = self.month.checked_add(9)? % 12;
+        let doy = ((153 * mp + 2) / 5).checked_add(self.day)?.checked_sub(1)?;
+        let doe = (yoe * 365 + yoe / 4 - yoe / 100).checked_add(doy)?;
+        let days = era
+            .checked_mul(146097)?
+            .checked_add(doe)?
+            .checked_sub(719468)?;
+
+        days.checked_mul(86400)?
+            .checked_add(self.hour.checked_mul(3600)?)?
+            .checked_add(self.minute.checked_mul(60)?)?
+            .checked_add(self.second)
+    }
 
-        Date::from(weekday.to_string(), date, time)
+    /// Formats the date the way dhcpd writes it,
+    /// `weekday year/month/day hour:minute:second`
+    pub fn to_dhcpd_string(&self) -> String {
+        format!(
+            "{} {}/{:0>2}/{:0>2} {:0>2}:{:0>2}:{:0>2}",
+            self.weekday, self.year, self.month, self.day, self.hour, self.minute, self.second,
+        )
     }
 
     pub fn new() -> Date {
@@ -86,25 +204,145 @@ impl Date {
             second: 0,
         }
     }
-    fn weekday_to_string(self) -> String {
-        match self.weekday {
-            0 => "Sunday".to_owned(),
-            1 => "Monday".to_owned(),
-            2 => "Tuesday".to_owned(),
-            3 => "Wednesday".to_owned(),
-            4 => "Thursday".to_owned(),
-            5 => "Friday".to_owned(),
-            6 => "Saturday".to_owned(),
-            _ => "Not a valid weekday".to_owned(),
+}
+
+impl Default for Date {
+    fn default() -> Date {
+        Date::new()
+    }
+}
+
+impl FromStr for Date {
+    type Err = ParseError;
+
+    /// Parses either an ISO 8601 / RFC 3339 date time
+    /// (`2024-05-01T12:00:00Z`) or the dhcpd format (`3 2024/05/01 12:00:00`).
+    /// Date times with an offset are converted to UTC, a missing time zone
+    /// designator is taken as UTC.
+    fn from_str(s: &str) -> Result<Date, ParseError> {
+        let s = s.trim();
+        let parts: Vec<&str> = s.split_whitespace().collect();
+
+        if parts.len() == 3 && parts[1].contains('/') {
+            if !s.chars().all(|c| c.is_ascii_digit() || " /:".contains(c)) {
+                return Err(ParseError::invalid_date(format!(
+                    "{:?} is not a valid dhcpd date",
+                    s
+                )));
+            }
+            return Date::from(parts[0], parts[1], parts[2]);
         }
+
+        parse_iso8601(s).map_err(ParseError::invalid_date)
+    }
+}
+
+/// Parses a UTC offset written `±HH`, `±HHMM` or `±HH:MM`, in seconds
+pub(crate) fn parse_utc_offset(o: &str) -> Option<i64> {
+    let sign = match o.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let o = &o[1..];
+    if !o.chars().all(|c| c.is_ascii_digit() || c == ':') {
+        return None;
+    }
+    let (h, m) = match o.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h, m),
+        Some(_) => return None,
+        None if o.len() == 4 => o.split_at(2),
+        None => (o, "00"),
+    };
+    if h.len() != 2 || m.contains(':') {
+        return None;
+    }
+    let (h, m) = (h.parse::<i64>().ok()?, m.parse::<i64>().ok()?);
+    if h > 23 || m > 59 {
+        return None;
     }
+    Some(sign * (h * 3600 + m * 60))
+}
+
+fn parse_iso8601(s: &str) -> Result<Date, String> {
+    let err = || format!("{:?} is not a valid ISO 8601 date", s);
+    let number = |v: &str| {
+        if v.is_empty() || !v.chars().all(|c| c.is_ascii_digit()) {
+            return Err(err());
+        }
+        v.parse::<i64>().map_err(|_| err())
+    };
+
+    let (date, time) = s.split_once(['T', 't', ' ']).ok_or_else(err)?;
+
+    let (time, offset) = if let Some(t) = time.strip_suffix(['Z', 'z']) {
+        (t, 0)
+    } else if let Some(i) = time.rfind(['+', '-']) {
+        let (t, o) = time.split_at(i);
+        (t, parse_utc_offset(o).ok_or_else(err)?)
+    } else {
+        (time, 0)
+    };
+    // fractional seconds are dropped
+    let time = time.split('.').next().unwrap_or(time);
+
+    let d: Vec<&str> = date.split('-').collect();
+    let t: Vec<&str> = time.split(':').collect();
+    if d.len() != 3 || t.len() != 3 {
+        return Err(err());
+    }
+
+    let local = Date {
+        weekday: 0,
+        year: number(d[0])?,
+        month: number(d[1])?,
+        day: number(d[2])?,
+        hour: number(t[0])?,
+        minute: number(t[1])?,
+        second: number(t[2])?,
+    };
+    if local.year > 9999 || local.month > 12 || local.day > 31 {
+        return Err(err());
+    }
+    if local.hour > 23 || local.minute > 59 || local.second > 59 {
+        return Err(err());
+    }
+
+    // out of range days and months would roll over, e.g. February 30th
+    let timestamp = local.to_unix_timestamp().ok_or_else(err)?;
+    let normalized = Date::from_unix_timestamp(timestamp);
+    if (normalized.year, normalized.month, normalized.day) != (local.year, local.month, local.day) {
+        return Err(err());
+    }
+
+    Ok(Date::from_unix_timestamp(
+        timestamp.checked_sub(offset).ok_or_else(err)?,
+    ))
 }
 
 impl fmt::Display for Date {
+    /// Formats the date in RFC 3339 (`YYYY-MM-DDTHH:MM:SSZ`), dates in
+    /// dhcpd files are always in UTC.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
-            f,"{} {}/{:0>2}/{:0>2} {:0>2}:{:0>2}:{:0>2}",
-            self.weekday_to_string(),
+            f,
+            "{:0>4}-{:0>2}-{:0>2}T{:0>2}:{:0>2}:{:0>2}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second,
+        )
+    }
+}
+
+impl cmp::PartialEq for Date {
+    fn eq(&self, other: &Date) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl cmp::Eq for Date {}
+
+impl std::hash::Hash for Date {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (
             self.year,
             self.month,
             self.day,
@@ -112,41 +350,354 @@ impl fmt::Display for Date {
             self.minute,
             self.second,
         )
+            .hash(state);
     }
 }
 
 impl cmp::PartialOrd for Date {
     fn partial_cmp(&self, other: &Date) -> Option<cmp::Ordering> {
-        if self.year != other.year {
-            return self.year.partial_cmp(&other.year);
-        }
+        Some(self.cmp(other))
+    }
+}
+
+impl cmp::Ord for Date {
+    fn cmp(&self, other: &Date) -> cmp::Ordering {
+        self.year
+            .cmp(&other.year)
+            .then(self.month.cmp(&other.month))
+            .then(self.day.cmp(&other.day))
+            .then(self.hour.cmp(&other.hour))
+            .then(self.minute.cmp(&other.minute))
+            .then(self.second.cmp(&other.second))
+    }
+}
+
+/// A span of time between two optional, inclusive bounds. A missing bound
+/// leaves the range open on that side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: Option<Date>,
+    pub end: Option<Date>,
+}
+
+impl DateRange {
+    pub fn new(start: Option<Date>, end: Option<Date>) -> DateRange {
+        DateRange { start, end }
+    }
 
-        if self.month != other.month {
-            return self.month.partial_cmp(&other.month);
+    pub fn contains(&self, date: &Date) -> bool {
+        self.start.is_none_or(|s| s <= *date) && self.end.is_none_or(|e| *date <= e)
+    }
+
+    /// Whether both ranges share at least one point in time
+    pub fn overlaps(&self, other: &DateRange) -> bool {
+        let starts_before_end = |start: Option<Date>, end: Option<Date>| match (start, end) {
+            (Some(s), Some(e)) => s <= e,
+            _ => true,
+        };
+
+        starts_before_end(self.start, other.end) && starts_before_end(other.start, self.end)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDateTime> for Date {
+    fn from(dt: chrono::NaiveDateTime) -> Date {
+        use chrono::{Datelike, Timelike};
+
+        Date {
+            weekday: i64::from(dt.weekday().num_days_from_sunday()),
+            year: i64::from(dt.year()),
+            month: i64::from(dt.month()),
+            day: i64::from(dt.day()),
+            hour: i64::from(dt.hour()),
+            minute: i64::from(dt.minute()),
+            second: i64::from(dt.second()),
         }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for Date {
+    fn from(dt: chrono::DateTime<chrono::Utc>) -> Date {
+        dt.naive_utc().into()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::convert::TryFrom<Date> for chrono::NaiveDateTime {
+    type Error = String;
 
-        if self.day != other.day {
-            return self.day.partial_cmp(&other.day);
+    /// Fails when the date or time fields are out of range, the weekday is
+    /// not checked.
+    fn try_from(date: Date) -> Result<chrono::NaiveDateTime, String> {
+        let to_u32 = |v: i64| u32::try_from(v).ok();
+        let year = i32::try_from(date.year).ok();
+
+        let d = year
+            .zip(to_u32(date.month))
+            .zip(to_u32(date.day))
+            .and_then(|((y, m), d)| chrono::NaiveDate::from_ymd_opt(y, m, d));
+        let t = to_u32(date.hour)
+            .zip(to_u32(date.minute))
+            .zip(to_u32(date.second))
+            .and_then(|((h, m), s)| chrono::NaiveTime::from_hms_opt(h, m, s));
+
+        match (d, t) {
+            (Some(d), Some(t)) => Ok(d.and_time(t)),
+            _ => Err(format!("{} is not a valid date", date)),
         }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::convert::TryFrom<Date> for chrono::DateTime<chrono::Utc> {
+    type Error = String;
+
+    fn try_from(date: Date) -> Result<chrono::DateTime<chrono::Utc>, String> {
+        chrono::NaiveDateTime::try_from(date).map(|dt| dt.and_utc())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for Date {
+    fn from(dt: time::OffsetDateTime) -> Date {
+        Date::from_unix_timestamp(dt.unix_timestamp())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::PrimitiveDateTime> for Date {
+    fn from(dt: time::PrimitiveDateTime) -> Date {
+        dt.assume_utc().into()
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Date> for time::OffsetDateTime {
+    /// Out of range fields roll over into the next unit (February 30th is
+    /// March 2nd), dates beyond what `time` supports are clamped.
+    fn from(date: Date) -> time::OffsetDateTime {
+        let ts = date.to_unix_timestamp();
 
-        if self.hour != other.hour {
-            return self.hour.partial_cmp(&other.hour);
+        ts.and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok())
+            .unwrap_or_else(|| {
+                if date.year < 0 {
+                    time::PrimitiveDateTime::MIN.assume_utc()
+                } else {
+                    time::PrimitiveDateTime::MAX.assume_utc()
+                }
+            })
+    }
+}
+
+/// Decodes the contents of a quoted string as written by dhcpd into raw
+/// bytes. Non-printable characters are written as three digit octal escapes
+/// (`\001`), other escaped characters (`\"`, `\\`) stand for themselves.
+pub fn unescape_bytes<S: AsRef<str>>(input: S) -> Result<Vec<u8>, String> {
+    let input_s = input.as_ref();
+    let mut result = Vec::new();
+    let mut it = input_s.bytes().peekable();
+
+    while let Some(b) = it.next() {
+        if b != b'\\' {
+            result.push(b);
+            continue;
         }
 
-        if self.minute != other.minute {
-            return self.minute.partial_cmp(&other.minute);
+        match it.next() {
+            Some(d @ b'0'..=b'7') => {
+                let mut value = u32::from(d - b'0');
+                for _ in 0..2 {
+                    match it.peek() {
+                        Some(&d @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(d - b'0');
+                            it.next();
+                        }
+                        _ => break,
+                    }
+                }
+                if value > 255 {
+                    return Err(format!("Octal escape out of range in {:?}", input_s));
+                }
+                result.push(value as u8);
+            }
+            Some(b't') => result.push(b'\t'),
+            Some(b'r') => result.push(b'\r'),
+            Some(b'n') => result.push(b'\n'),
+            Some(b'b') => result.push(0x08),
+            Some(c) => result.push(c),
+            None => return Err(format!("Dangling escape at the end of {:?}", input_s)),
         }
+    }
 
-        if self.second != other.second {
-            return self.second.partial_cmp(&other.second);
+    Ok(result)
+}
+
+/// Parses a colon separated list of hexadecimal octets (`1:c:ce:ff`)
+pub fn parse_hex_bytes<S: AsRef<str>>(input: S) -> Result<Vec<u8>, String> {
+    let input_s = input.as_ref();
+
+    input_s
+        .split(':')
+        .map(|part| {
+            if part.is_empty() || part.len() > 2 {
+                return Err(format!("{:?} is not a list of hexadecimal octets", input_s));
+            }
+            u8::from_str_radix(part, 16)
+                .map_err(|_| format!("{:?} is not a list of hexadecimal octets", input_s))
+        })
+        .collect()
+}
+
+/// Formats bytes as colon separated, zero padded, lowercase hexadecimal
+/// octets (`01:0c:ce:ff`)
+pub fn format_hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// Hardware address. dhcpd writes them as colon separated hexadecimal octets
+/// that may lack zero padding (`0:1b:c`), the address is kept as bytes so that
+/// such variants compare equal regardless of case and padding.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddr(Vec<u8>);
+
+impl MacAddr {
+    pub fn new(bytes: Vec<u8>) -> MacAddr {
+        MacAddr(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = String;
+
+    /// Parses octets separated by colons or dashes (`0A-0B-0C`)
+    fn from_str(s: &str) -> Result<MacAddr, String> {
+        parse_hex_bytes(s.replace('-', ":")).map(MacAddr)
+    }
+}
+
+/// Formats the address as zero padded, lowercase octets (`0a:0b:0c`)
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_hex_bytes(&self.0))
+    }
+}
+
+/// Compares with an address written as text, which is equal when it parses
+/// to the same octets
+impl PartialEq<str> for MacAddr {
+    fn eq(&self, other: &str) -> bool {
+        other
+            .parse::<MacAddr>()
+            .map(|m| m == *self)
+            .unwrap_or(false)
+    }
+}
+
+impl PartialEq<&str> for MacAddr {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Client identifier (`uid`). dhcpd writes them either as a quoted string
+/// with octal escapes or as colon separated hexadecimal octets, depending on
+/// its `lease-id-format`. The identifier is compared by its bytes, so both
+/// forms of the same uid are equal, while the text is kept as written.
+#[derive(Clone, Debug)]
+pub struct Uid {
+    raw: String,
+    bytes: Vec<u8>,
+}
+
+impl Uid {
+    /// Builds a uid from raw bytes, written as hexadecimal octets
+    pub fn new(bytes: Vec<u8>) -> Uid {
+        Uid {
+            raw: format_hex_bytes(&bytes),
+            bytes,
         }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
 
-        None
+    /// The identifier as it was written in the file
+    pub fn as_str(&self) -> &str {
+        &self.raw
     }
 }
 
-impl cmp::Ord for Date {
-    fn cmp(&self, other: &Date) -> cmp::Ordering {
-        return self.partial_cmp(other).unwrap();
+impl FromStr for Uid {
+    type Err = String;
+
+    /// Parses a quoted string or hexadecimal octets. Any other unquoted word
+    /// is taken literally.
+    fn from_str(s: &str) -> Result<Uid, String> {
+        let quoted = s.len() >= 2 && s.starts_with('"') && s.ends_with('"');
+        let bytes = match decode_identifier(s) {
+            Ok(bytes) => bytes,
+            Err(e) if quoted => return Err(e),
+            Err(_) => s.as_bytes().to_vec(),
+        };
+
+        Ok(Uid {
+            raw: s.to_owned(),
+            bytes,
+        })
+    }
+}
+
+/// Formats the uid as zero padded, lowercase hexadecimal octets
+impl fmt::Display for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_hex_bytes(&self.bytes))
+    }
+}
+
+impl PartialEq for Uid {
+    fn eq(&self, other: &Uid) -> bool {
+        self.bytes == other.bytes
     }
 }
+
+impl Eq for Uid {}
+
+impl std::hash::Hash for Uid {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bytes.hash(state);
+    }
+}
+
+/// Compares with a uid written as text, in any of its forms
+impl PartialEq<str> for Uid {
+    fn eq(&self, other: &str) -> bool {
+        other.parse::<Uid>().map(|u| u == *self).unwrap_or(false)
+    }
+}
+
+impl PartialEq<&str> for Uid {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Decodes an identifier written either as a quoted string with escapes or as
+/// colon separated hexadecimal octets, as dhcpd does for uids and DUIDs
+pub(crate) fn decode_identifier(input: &str) -> Result<Vec<u8>, String> {
+    if input.len() >= 2 && input.starts_with('"') && input.ends_with('"') {
+        return unescape_bytes(&input[1..input.len() - 1]);
+    }
+
+    parse_hex_bytes(input)
+}
@@ -0,0 +1,1854 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::iter::Peekable;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::ops::Index;
+use std::str::FromStr;
+
+use crate::error::ParseError;
+use crate::leases::parse_hardware;
+use crate::leases::parse_raw_statement;
+use crate::leases::parse_value;
+use crate::leases::unquote;
+use crate::leases::Hardware;
+use crate::leases::Lease;
+use crate::lex::end_span;
+use crate::lex::lex;
+use crate::lex::lex_spanned;
+use crate::lex::LexItem;
+use crate::parser::Span;
+
+/// Server configuration read from a `dhcpd.conf` file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DhcpdConfig {
+    /// Top level parameters, options and declarations
+    pub global: Scope,
+}
+
+impl DhcpdConfig {
+    /// Returns every scope of the configuration, each followed by the scopes
+    /// nested in it
+    fn scopes(&self) -> Vec<&Scope> {
+        fn collect<'a>(scope: &'a Scope, result: &mut Vec<&'a Scope>) {
+            result.push(scope);
+            for nested in scope.nested_scopes() {
+                collect(nested, result);
+            }
+        }
+
+        let mut result = Vec::new();
+        collect(&self.global, &mut result);
+        result
+    }
+
+    /// Returns the subnets declared anywhere in the configuration, including
+    /// the ones within shared networks and groups
+    pub fn subnets(&self) -> Vec<&Subnet> {
+        self.scopes().into_iter().flat_map(|s| &s.subnets).collect()
+    }
+
+    /// Returns the IPv6 subnets declared anywhere in the configuration
+    pub fn subnets6(&self) -> Vec<&Subnet6> {
+        self.scopes()
+            .into_iter()
+            .flat_map(|s| &s.subnets6)
+            .collect()
+    }
+
+    pub fn shared_networks(&self) -> Vec<&SharedNetwork> {
+        self.scopes()
+            .into_iter()
+            .flat_map(|s| &s.shared_networks)
+            .collect()
+    }
+
+    pub fn pools(&self) -> Vec<&Pool> {
+        self.scopes().into_iter().flat_map(|s| &s.pools).collect()
+    }
+
+    pub fn hosts(&self) -> Vec<&Host> {
+        self.scopes().into_iter().flat_map(|s| &s.hosts).collect()
+    }
+
+    pub fn classes(&self) -> Vec<&Class> {
+        self.scopes().into_iter().flat_map(|s| &s.classes).collect()
+    }
+
+    pub fn failover_peers(&self) -> Vec<&FailoverPeer> {
+        self.scopes()
+            .into_iter()
+            .flat_map(|s| &s.failover_peers)
+            .collect()
+    }
+
+    pub fn host(&self, name: &str) -> Option<&Host> {
+        self.hosts().into_iter().find(|h| h.name == name)
+    }
+
+    pub fn failover_peer(&self, name: &str) -> Option<&FailoverPeer> {
+        self.failover_peers().into_iter().find(|p| p.name == name)
+    }
+
+    /// Returns the subnet the address belongs to
+    pub fn subnet_for(&self, ip: Ipv4Addr) -> Option<&Subnet> {
+        self.subnets().into_iter().find(|s| s.contains(ip))
+    }
+
+    /// Returns the IPv6 subnet the address belongs to
+    pub fn subnet6_for(&self, ip: Ipv6Addr) -> Option<&Subnet6> {
+        self.subnets6().into_iter().find(|s| s.contains(ip))
+    }
+
+    /// Attributes each lease to the subnet and pool its address belongs to
+    pub fn locate_leases<'a>(&'a self, leases: &'a [Lease]) -> Vec<LeaseLocation<'a>> {
+        let pools = self.pools();
+        let ranges: Vec<&Range> = self.scopes().into_iter().flat_map(|s| &s.ranges).collect();
+
+        leases
+            .iter()
+            .map(|lease| {
+                let ip = match lease.ip {
+                    IpAddr::V4(ip) => ip,
+                    IpAddr::V6(_) => {
+                        return LeaseLocation {
+                            lease,
+                            subnet: None,
+                            pool: None,
+                            in_range: false,
+                        }
+                    }
+                };
+
+                LeaseLocation {
+                    lease,
+                    subnet: self.subnet_for(ip),
+                    pool: pools.iter().find(|p| p.contains(ip)).copied(),
+                    in_range: ranges.iter().any(|r| r.contains(ip)),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the leases whose address is not in any configured range
+    pub fn leases_outside_ranges<'a>(&'a self, leases: &'a [Lease]) -> Vec<&'a Lease> {
+        self.locate_leases(leases)
+            .into_iter()
+            .filter(|l| !l.in_range)
+            .map(|l| l.lease)
+            .collect()
+    }
+
+    /// Returns the parameters and options applying to a host, including the
+    /// ones inherited from the enclosing declarations. Values set closer to
+    /// the host override inherited ones.
+    pub fn host_parameters(&self, name: &str) -> Option<ResolvedParameters> {
+        let mut path = vec![&self.global];
+        let host = find_host(&self.global, name, &mut path)?;
+        path.push(&host.scope);
+
+        let mut resolved = ResolvedParameters::default();
+        for scope in path {
+            resolved.parameters.merge(&scope.parameters);
+            resolved.options.extend(scope.options.clone());
+        }
+        Some(resolved)
+    }
+
+    /// Returns the top level definition of an option, named either `name`
+    /// for an option of the dhcp space or `space.name`
+    pub fn option_definition(&self, name: &str) -> Option<&OptionDefinition> {
+        self.global
+            .option_definitions
+            .iter()
+            .find(|d| d.full_name() == name)
+    }
+}
+
+impl FromStr for DhcpdConfig {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<DhcpdConfig, ParseError> {
+        parse(s)
+    }
+}
+
+/// Looks for a host declared in the scope or in the nested declarations,
+/// adding the scopes leading to it to `path`
+fn find_host<'a>(scope: &'a Scope, name: &str, path: &mut Vec<&'a Scope>) -> Option<&'a Host> {
+    if let Some(host) = scope.hosts.iter().find(|h| h.name == name) {
+        return Some(host);
+    }
+
+    for nested in scope.nested_scopes() {
+        path.push(nested);
+        if let Some(host) = find_host(nested, name, path) {
+            return Some(host);
+        }
+        path.pop();
+    }
+
+    None
+}
+
+/// Place of a lease in the configuration, see `DhcpdConfig::locate_leases`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeaseLocation<'a> {
+    pub lease: &'a Lease,
+    /// Subnet the address of the lease belongs to
+    pub subnet: Option<&'a Subnet>,
+    /// Pool whose ranges contain the address of the lease
+    pub pool: Option<&'a Pool>,
+    /// Whether the address is in any range, within a pool or not
+    pub in_range: bool,
+}
+
+/// Parameters and options in effect in a scope once inheritance is applied
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResolvedParameters {
+    pub parameters: Parameters,
+    pub options: HashMap<String, String>,
+}
+
+/// Parameter statements in the order they are written. A parameter can be
+/// repeated, as `allow` and `deny` usually are, lookups by name return the
+/// last value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Parameters(Vec<(String, String)>);
+
+impl Parameters {
+    pub fn new() -> Parameters {
+        Parameters(Vec::new())
+    }
+
+    /// Adds a parameter, keeping the values it already has
+    pub fn insert(&mut self, name: String, value: String) {
+        self.0.push((name, value));
+    }
+
+    /// Last value of a parameter
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of a parameter, in the order they are written
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.0.iter().any(|(n, _)| n == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds the parameters of `other`, which replace the values of the
+    /// parameters with the same name
+    pub fn merge(&mut self, other: &Parameters) {
+        self.0.retain(|(n, _)| !other.contains_key(n));
+        self.0.extend(other.0.iter().cloned());
+    }
+}
+
+impl Index<&str> for Parameters {
+    type Output = String;
+
+    fn index(&self, name: &str) -> &String {
+        self.0
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+            .unwrap_or_else(|| panic!("No parameter named {}", name))
+    }
+}
+
+/// Content of a configuration block, or of the whole file at the top level.
+/// Declarations nested in the block are kept in their own scope, so that
+/// the parameters they inherit can be resolved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scope {
+    /// Parameter statements (`default-lease-time 600;`), values kept as
+    /// written. Flags map to `true` (`authoritative;`) or `false`
+    /// (`not authoritative;`).
+    pub parameters: Parameters,
+    /// `option name value;` statements, values kept as written
+    pub options: HashMap<String, String>,
+    /// Option spaces declared with `option space name;`
+    pub option_spaces: Vec<OptionSpace>,
+    /// Options declared with `option name code N = type;`
+    pub option_definitions: Vec<OptionDefinition>,
+    /// Address ranges (`range 10.0.0.10 10.0.0.100;`)
+    pub ranges: Vec<Range>,
+    /// IPv6 address ranges (`range6 2001:db8::10 2001:db8::100;`)
+    pub ranges6: Vec<Range6>,
+    /// Delegated prefix ranges (`prefix6 2001:db8:100:: 2001:db8:f00:: /56;`)
+    pub prefixes6: Vec<Prefix6>,
+    pub subnets: Vec<Subnet>,
+    pub subnets6: Vec<Subnet6>,
+    pub shared_networks: Vec<SharedNetwork>,
+    pub hosts: Vec<Host>,
+    pub groups: Vec<Group>,
+    pub pools: Vec<Pool>,
+    /// `pool6 { ... }` declarations
+    pub pools6: Vec<Pool>,
+    pub classes: Vec<Class>,
+    pub subclasses: Vec<Subclass>,
+    pub conditionals: Vec<Conditional>,
+    /// TSIG keys used to sign DNS updates
+    pub keys: Vec<Key>,
+    /// DNS zones the server sends updates to
+    pub zones: Vec<Zone>,
+    pub failover_peers: Vec<FailoverPeer>,
+    /// Statements that are not understood, kept as raw text
+    pub statements: Vec<String>,
+}
+
+impl Scope {
+    /// Returns the value of a parameter of the scope converted to `T`, or
+    /// `None` when it is not set or cannot be converted
+    pub fn parameter<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.parameters.get(name)?.parse().ok()
+    }
+
+    /// `default-lease-time`, in seconds
+    pub fn default_lease_time(&self) -> Option<u32> {
+        self.parameter("default-lease-time")
+    }
+
+    /// `max-lease-time`, in seconds
+    pub fn max_lease_time(&self) -> Option<u32> {
+        self.parameter("max-lease-time")
+    }
+
+    /// `min-lease-time`, in seconds
+    pub fn min_lease_time(&self) -> Option<u32> {
+        self.parameter("min-lease-time")
+    }
+
+    /// Whether the server is declared `authoritative` or `not authoritative`
+    pub fn authoritative(&self) -> Option<bool> {
+        self.parameter("authoritative")
+    }
+
+    pub fn ddns_update_style(&self) -> Option<DdnsUpdateStyle> {
+        self.parameter("ddns-update-style")
+    }
+
+    /// Scopes of the declarations directly nested in this one
+    fn nested_scopes(&self) -> Vec<&Scope> {
+        let subnets = self.subnets.iter().map(|s| &s.scope);
+        let subnets6 = self.subnets6.iter().map(|s| &s.scope);
+        let shared_networks = self.shared_networks.iter().map(|n| &n.scope);
+        let groups = self.groups.iter().map(|g| &g.scope);
+        let pools = self.pools.iter().chain(&self.pools6).map(|p| &p.scope);
+        subnets
+            .chain(subnets6)
+            .chain(shared_networks)
+            .chain(groups)
+            .chain(pools)
+            .collect()
+    }
+}
+
+/// Value of the `ddns-update-style` parameter
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DdnsUpdateStyle {
+    None,
+    AdHoc,
+    Interim,
+    Standard,
+}
+
+impl FromStr for DdnsUpdateStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DdnsUpdateStyle, String> {
+        match s {
+            "none" => Ok(DdnsUpdateStyle::None),
+            "ad-hoc" => Ok(DdnsUpdateStyle::AdHoc),
+            "interim" => Ok(DdnsUpdateStyle::Interim),
+            "standard" => Ok(DdnsUpdateStyle::Standard),
+            _ => Err(format!("Unknown DDNS update style '{}'", s)),
+        }
+    }
+}
+
+/// `range [dynamic-bootp] low [high];` statement. A range made of a single
+/// address has the same `start` and `end`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Range {
+    pub dynamic_bootp: bool,
+    pub start: Ipv4Addr,
+    pub end: Ipv4Addr,
+}
+
+impl Range {
+    pub fn new(start: Ipv4Addr, end: Ipv4Addr) -> Range {
+        Range {
+            dynamic_bootp: false,
+            start,
+            end,
+        }
+    }
+
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.start <= ip && ip <= self.end
+    }
+
+    /// Number of addresses in the range
+    pub fn size(&self) -> u64 {
+        if self.end < self.start {
+            return 0;
+        }
+        u64::from(u32::from(self.end) - u32::from(self.start)) + 1
+    }
+}
+
+impl FromStr for Range {
+    type Err = String;
+
+    /// Parses either two addresses (`10.0.0.10-10.0.0.20`) or a network in
+    /// CIDR notation (`10.0.0.0/24`), which covers all of its addresses
+    fn from_str(s: &str) -> Result<Range, String> {
+        let invalid = || format!("'{}' is not a valid address range", s);
+
+        if let Some((network, len)) = s.split_once('/') {
+            let network: Ipv4Addr = network.trim().parse().map_err(|_| invalid())?;
+            let len: u32 = len.trim().parse().map_err(|_| invalid())?;
+            if len > 32 {
+                return Err(invalid());
+            }
+            let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+            let start = u32::from(network) & mask;
+
+            return Ok(Range::new(
+                Ipv4Addr::from(start),
+                Ipv4Addr::from(start | !mask),
+            ));
+        }
+
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        Ok(Range::new(
+            start.trim().parse().map_err(|_| invalid())?,
+            end.trim().parse().map_err(|_| invalid())?,
+        ))
+    }
+}
+
+/// `subnet 10.0.0.0 netmask 255.255.255.0 { ... }` declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subnet {
+    pub network: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub scope: Scope,
+}
+
+impl Subnet {
+    pub fn new(network: Ipv4Addr, netmask: Ipv4Addr) -> Subnet {
+        Subnet {
+            network,
+            netmask,
+            scope: Scope::default(),
+        }
+    }
+
+    /// Whether the address belongs to the subnet
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        u32::from(ip) & u32::from(self.netmask) == u32::from(self.network)
+    }
+}
+
+/// `range6` statement, either `range6 low high;` or `range6 network/len
+/// [temporary];`, the latter being converted into the first and last
+/// addresses of the network
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Range6 {
+    pub start: Ipv6Addr,
+    pub end: Ipv6Addr,
+    /// Range of temporary addresses (IA_TA)
+    pub temporary: bool,
+}
+
+impl Range6 {
+    pub fn contains(&self, ip: Ipv6Addr) -> bool {
+        self.start <= ip && ip <= self.end
+    }
+}
+
+/// `prefix6 low high /len;` statement, the prefixes of length `prefix_len`
+/// between `start` and `end` being delegated to clients
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prefix6 {
+    pub start: Ipv6Addr,
+    pub end: Ipv6Addr,
+    pub prefix_len: u8,
+}
+
+/// `subnet6 2001:db8::/64 { ... }` declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subnet6 {
+    pub network: Ipv6Addr,
+    pub prefix_len: u8,
+    pub scope: Scope,
+}
+
+impl Subnet6 {
+    pub fn new(network: Ipv6Addr, prefix_len: u8) -> Subnet6 {
+        Subnet6 {
+            network,
+            prefix_len,
+            scope: Scope::default(),
+        }
+    }
+
+    /// Whether the address belongs to the subnet
+    pub fn contains(&self, ip: Ipv6Addr) -> bool {
+        let mask = prefix_mask(self.prefix_len);
+        u128::from(ip) & mask == u128::from(self.network) & mask
+    }
+}
+
+fn prefix_mask(prefix_len: u8) -> u128 {
+    match prefix_len {
+        0 => 0,
+        len => u128::MAX << (128 - u32::from(len.min(128))),
+    }
+}
+
+/// Parses a `2001:db8::/64` network
+fn parse_network6(s: &str) -> Result<(Ipv6Addr, u8), String> {
+    let invalid = || format!("'{}' is not a valid IPv6 network", s);
+    let pos = s.find('/').ok_or_else(invalid)?;
+    let network = s[..pos].parse().map_err(|_| invalid())?;
+    let prefix_len = s[pos + 1..].parse().map_err(|_| invalid())?;
+    if prefix_len > 128 {
+        return Err(invalid());
+    }
+
+    Ok((network, prefix_len))
+}
+
+/// `shared-network name { ... }` declaration, grouping the subnets sharing
+/// the same physical network
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedNetwork {
+    pub name: String,
+    pub scope: Scope,
+}
+
+impl SharedNetwork {
+    pub fn new<S: Into<String>>(name: S) -> SharedNetwork {
+        SharedNetwork {
+            name: name.into(),
+            scope: Scope::default(),
+        }
+    }
+}
+
+/// `host name { ... }` declaration, usually reserving an address for a client
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Host {
+    pub name: String,
+    pub hardware: Option<Hardware>,
+    /// Addresses of `fixed-address`, as written (IP addresses or domain names)
+    pub fixed_addresses: Vec<String>,
+    pub scope: Scope,
+}
+
+impl Host {
+    pub fn new<S: Into<String>>(name: S) -> Host {
+        Host {
+            name: name.into(),
+            hardware: None,
+            fixed_addresses: Vec::new(),
+            scope: Scope::default(),
+        }
+    }
+
+    /// Returns the unquoted value of `option host-name`
+    pub fn host_name(&self) -> Option<String> {
+        self.scope
+            .options
+            .get("host-name")
+            .map(|v| unquote(v.clone()))
+    }
+}
+
+/// `group [name] { ... }` declaration, applying its parameters to the
+/// declarations it contains
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Group {
+    pub name: Option<String>,
+    pub scope: Scope,
+}
+
+/// `pool { ... }` declaration, holding address ranges along with the
+/// clients allowed to get addresses from them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pool {
+    /// `allow` statements, as written without the keyword
+    /// (`members of "class"`, `unknown-clients`, ...)
+    pub allow: Vec<String>,
+    /// `deny` statements, as written without the keyword
+    pub deny: Vec<String>,
+    /// Name of the failover peer serving the pool (`failover peer "name";`)
+    pub failover_peer: Option<String>,
+    pub scope: Scope,
+}
+
+impl Pool {
+    /// Number of addresses in the ranges of the pool
+    pub fn size(&self) -> u64 {
+        self.scope.ranges.iter().map(|r| r.size()).sum()
+    }
+
+    /// Whether the address is in one of the ranges of the pool
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.scope.ranges.iter().any(|r| r.contains(ip))
+    }
+}
+
+/// `match` statement of a class
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClassMatch {
+    /// `match if <boolean expression>;`, clients matching the expression are
+    /// members of the class
+    If(Vec<String>),
+    /// `match <data expression>;`, the value is looked up in the subclasses
+    Value(Vec<String>),
+}
+
+/// `class "name" { ... }` declaration. Expressions are kept as the list of
+/// their tokens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Class {
+    pub name: String,
+    pub matches: Option<ClassMatch>,
+    /// Expression of `spawn with`, spawning a subclass per distinct value
+    pub spawn_with: Option<Vec<String>>,
+    /// `lease limit`, the number of leases members of the class may hold
+    pub lease_limit: Option<u32>,
+    pub scope: Scope,
+}
+
+impl Class {
+    pub fn new<S: Into<String>>(name: S) -> Class {
+        Class {
+            name: name.into(),
+            matches: None,
+            spawn_with: None,
+            lease_limit: None,
+            scope: Scope::default(),
+        }
+    }
+}
+
+/// `subclass "class" data [{ ... }]` declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subclass {
+    /// Name of the parent class
+    pub class: String,
+    /// Data matched against the `match` expression of the class, as written
+    pub data: String,
+    pub scope: Scope,
+}
+
+/// `option space name [code width N] [length width N] [hash size N];`
+/// declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionSpace {
+    pub name: String,
+    /// Width of the option codes, in bytes
+    pub code_width: Option<u8>,
+    /// Width of the option lengths, in bytes
+    pub length_width: Option<u8>,
+    pub hash_size: Option<u32>,
+}
+
+impl FromStr for OptionSpace {
+    type Err = String;
+
+    /// Parses the raw text of an `option space` statement
+    fn from_str(s: &str) -> Result<OptionSpace, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        if words.len() < 3 || words[0] != "option" || words[1] != "space" {
+            return Err(format!("Invalid option space declaration '{}'", s));
+        }
+
+        let mut space = OptionSpace {
+            name: unquote(words[2].to_owned()),
+            code_width: None,
+            length_width: None,
+            hash_size: None,
+        };
+
+        let invalid = || format!("Invalid option space declaration '{}'", s);
+        for setting in words[3..].chunks(3) {
+            let value = setting.get(2).ok_or_else(invalid)?;
+            match (setting[0], setting[1]) {
+                ("code", "width") => {
+                    space
+                        .code_width
+                        .replace(value.parse().map_err(|_| invalid())?);
+                }
+                ("length", "width") => {
+                    space
+                        .length_width
+                        .replace(value.parse().map_err(|_| invalid())?);
+                }
+                ("hash", "size") => {
+                    space
+                        .hash_size
+                        .replace(value.parse().map_err(|_| invalid())?);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(space)
+    }
+}
+
+/// Format of the value of an option, as given in its definition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionType {
+    IpAddress,
+    Ip6Address,
+    Boolean,
+    /// `[un]signed integer <bits>`
+    Integer {
+        signed: bool,
+        bits: u8,
+    },
+    Text,
+    String,
+    DomainName,
+    DomainList,
+    /// Options of another space, carried inside this one
+    Encapsulate(String),
+    /// `array of <type>`
+    Array(Box<OptionType>),
+    /// `{ <type>, <type>, ... }`
+    Record(Vec<OptionType>),
+    Other(String),
+}
+
+impl OptionType {
+    fn parse<'a, I: Iterator<Item = &'a str>>(
+        words: &mut Peekable<I>,
+    ) -> Result<OptionType, String> {
+        let word = words.next().ok_or("Option type expected")?;
+        let option_type = match word {
+            "ip-address" => OptionType::IpAddress,
+            "ip6-address" => OptionType::Ip6Address,
+            "boolean" => OptionType::Boolean,
+            "text" => OptionType::Text,
+            "string" => OptionType::String,
+            "domain-name" => OptionType::DomainName,
+            "domain-list" => {
+                if words.peek() == Some(&"compressed") {
+                    words.next();
+                }
+                OptionType::DomainList
+            }
+            "signed" | "unsigned" | "integer" => {
+                let signed = word != "unsigned";
+                if word != "integer" && words.next() != Some("integer") {
+                    return Err(format!("Expected integer after {}", word));
+                }
+                let bits = words
+                    .next()
+                    .and_then(|b| b.parse::<u8>().ok())
+                    .ok_or("Integer width expected")?;
+                OptionType::Integer { signed, bits }
+            }
+            "encapsulate" => {
+                OptionType::Encapsulate(words.next().ok_or("Option space expected")?.to_owned())
+            }
+            "array" => {
+                if words.next() != Some("of") {
+                    return Err("Expected of after array".to_owned());
+                }
+                OptionType::Array(Box::new(OptionType::parse(words)?))
+            }
+            "{" => {
+                let mut fields = vec![OptionType::parse(words)?];
+                loop {
+                    match words.next() {
+                        Some(",") => fields.push(OptionType::parse(words)?),
+                        Some("}") => break,
+                        w => return Err(format!("Expected , or }} in record, found {:?}", w)),
+                    }
+                }
+                OptionType::Record(fields)
+            }
+            _ => OptionType::Other(word.to_owned()),
+        };
+
+        Ok(option_type)
+    }
+}
+
+impl FromStr for OptionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OptionType, String> {
+        let spaced = s
+            .replace(',', " , ")
+            .replace('{', " { ")
+            .replace('}', " } ");
+        let mut words = spaced.split_whitespace().peekable();
+        let option_type = OptionType::parse(&mut words)?;
+
+        match words.next() {
+            None => Ok(option_type),
+            Some(w) => Err(format!("Unexpected '{}' after option type", w)),
+        }
+    }
+}
+
+/// `option [space.]name code N = type;` declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionDefinition {
+    /// Space of the option, `None` for the standard dhcp space
+    pub space: Option<String>,
+    pub name: String,
+    pub code: u32,
+    pub option_type: OptionType,
+}
+
+impl OptionDefinition {
+    /// Name of the option, prefixed with its space if any
+    pub fn full_name(&self) -> String {
+        match &self.space {
+            Some(space) => format!("{}.{}", space, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl FromStr for OptionDefinition {
+    type Err = String;
+
+    /// Parses the raw text of an `option name code N = type` statement
+    fn from_str(s: &str) -> Result<OptionDefinition, String> {
+        let words: Vec<&str> = s.splitn(6, ' ').collect();
+        if words.len() < 6 || words[0] != "option" || words[2] != "code" || words[4] != "=" {
+            return Err(format!("Invalid option definition '{}'", s));
+        }
+
+        let (space, name) = match words[1].find('.') {
+            Some(pos) => (Some(words[1][..pos].to_owned()), &words[1][pos + 1..]),
+            None => (None, words[1]),
+        };
+
+        Ok(OptionDefinition {
+            space,
+            name: name.to_owned(),
+            code: words[3]
+                .parse()
+                .map_err(|_| format!("Invalid option code '{}'", words[3]))?,
+            option_type: words[5].parse()?,
+        })
+    }
+}
+
+/// `key name { algorithm ...; secret ...; }` declaration of a TSIG key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub name: String,
+    pub algorithm: Option<String>,
+    /// Base64 encoded secret
+    pub secret: Option<String>,
+}
+
+/// `zone name { primary ...; key ...; }` declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Zone {
+    pub name: String,
+    /// Server receiving the updates, as written
+    pub primary: Option<String>,
+    pub secondary: Option<String>,
+    /// Name of the key signing the updates
+    pub key: Option<String>,
+    /// Any other statements of the declaration, kept as raw text
+    pub statements: Vec<String>,
+}
+
+/// Role of the server in a failover pair
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailoverRole {
+    Primary,
+    Secondary,
+}
+
+/// `failover peer "name" { ... }` declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailoverPeer {
+    pub name: String,
+    pub role: Option<FailoverRole>,
+    /// Address the server listens on for failover messages, as written
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    /// Address of the peer, as written
+    pub peer_address: Option<String>,
+    pub peer_port: Option<u16>,
+    /// `max-response-delay`, in seconds
+    pub max_response_delay: Option<u32>,
+    pub max_unacked_updates: Option<u32>,
+    /// Maximum client lead time, in seconds
+    pub mclt: Option<u32>,
+    /// Share of the clients served by the primary, out of 256
+    pub split: Option<u8>,
+    /// Hash bucket assignment given instead of `split`, as written
+    pub hba: Option<String>,
+    /// `load balance max seconds`
+    pub load_balance_max_seconds: Option<u32>,
+    /// Any other statements of the declaration, kept as raw text
+    pub statements: Vec<String>,
+}
+
+impl FailoverPeer {
+    pub fn new<S: Into<String>>(name: S) -> FailoverPeer {
+        FailoverPeer {
+            name: name.into(),
+            role: None,
+            address: None,
+            port: None,
+            peer_address: None,
+            peer_port: None,
+            max_response_delay: None,
+            max_unacked_updates: None,
+            mclt: None,
+            split: None,
+            hba: None,
+            load_balance_max_seconds: None,
+            statements: Vec::new(),
+        }
+    }
+}
+
+/// `if condition { ... } elsif condition { ... } else { ... }` statement.
+/// Conditions are kept as the list of their tokens.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Conditional {
+    /// The `if` and `elsif` branches, in order
+    pub branches: Vec<Branch>,
+    /// Content of the `else` block
+    pub otherwise: Option<Scope>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Branch {
+    pub condition: Vec<String>,
+    pub scope: Scope,
+}
+
+/// Parses the content of a block. The iterator is expected to point at the
+/// first token after the opening brace and is left pointing at the closing
+/// brace, or at the end of input for the top level scope.
+fn parse_scope<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    scope: &mut Scope,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        if nc == &LexItem::Paren('}') {
+            return Ok(());
+        }
+
+        parse_statement(scope, iter)?;
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Parses a statement or declaration of a block. The iterator is expected to
+/// point at its first token and is left pointing at the terminating semicolon
+/// or closing brace.
+fn parse_statement<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    scope: &mut Scope,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    if let Some(&nc) = iter.peek() {
+        match nc.to_string().as_str() {
+            ";" => (),
+            "subnet" => {
+                let mut subnet = Subnet::new(
+                    parse_address(iter, "Subnet address")?,
+                    Ipv4Addr::UNSPECIFIED,
+                );
+
+                iter.next();
+                match iter.peek() {
+                    Some(t) if t.to_string() == "netmask" => (),
+                    t => {
+                        return Err(ParseError::unexpected(format!(
+                            "Expected netmask, found {:?}",
+                            t
+                        )))
+                    }
+                }
+                subnet.netmask = parse_address(iter, "Netmask")?;
+
+                parse_block_start(iter, "subnet")?;
+                parse_scope(&mut subnet.scope, iter)?;
+                parse_block_end(iter)?;
+                scope.subnets.push(subnet);
+            }
+            "shared-network" => {
+                iter.next();
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the shared network expected"))?
+                    .to_string();
+                let mut network = SharedNetwork::new(unquote(name));
+
+                parse_block_start(iter, "shared-network")?;
+                parse_scope(&mut network.scope, iter)?;
+                parse_block_end(iter)?;
+                scope.shared_networks.push(network);
+            }
+            "class" => {
+                iter.next();
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the class expected"))?
+                    .to_string();
+                let mut class = Class::new(unquote(name));
+
+                parse_block_start(iter, "class")?;
+                parse_class(&mut class, iter)?;
+                parse_block_end(iter)?;
+                scope.classes.push(class);
+            }
+            "subclass" => {
+                iter.next();
+                let class = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the class expected"))?
+                    .to_string();
+                iter.next();
+                let data = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Subclass data expected"))?
+                    .to_string();
+                let mut subclass = Subclass {
+                    class: unquote(class),
+                    data,
+                    scope: Scope::default(),
+                };
+
+                iter.next();
+                match iter.peek() {
+                    Some(LexItem::Endl) => (),
+                    Some(LexItem::Paren('{')) => {
+                        iter.next();
+                        parse_scope(&mut subclass.scope, iter)?;
+                        parse_block_end(iter)?;
+                    }
+                    t => {
+                        return Err(ParseError::unexpected(format!(
+                            "Expected semicolon or '{{', found {:?}",
+                            t
+                        )))
+                    }
+                }
+                scope.subclasses.push(subclass);
+            }
+            "host" => {
+                iter.next();
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the host expected"))?
+                    .to_string();
+                let mut host = Host::new(unquote(name));
+
+                parse_block_start(iter, "host")?;
+                parse_host(&mut host, iter)?;
+                parse_block_end(iter)?;
+                scope.hosts.push(host);
+            }
+            "group" => {
+                let mut group = Group::default();
+                if let Some(t) = iter.clone().nth(1) {
+                    if t != &LexItem::Paren('{') {
+                        iter.next();
+                        group.name.replace(unquote(t.to_string()));
+                    }
+                }
+
+                parse_block_start(iter, "group")?;
+                parse_scope(&mut group.scope, iter)?;
+                parse_block_end(iter)?;
+                scope.groups.push(group);
+            }
+            "pool" => {
+                let mut pool = Pool::default();
+
+                parse_block_start(iter, "pool")?;
+                parse_pool(&mut pool, iter)?;
+                parse_block_end(iter)?;
+                scope.pools.push(pool);
+            }
+            "range" => {
+                scope.ranges.push(parse_range(iter)?);
+            }
+            "subnet6" => {
+                iter.next();
+                let network = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Subnet address expected"))?
+                    .to_string();
+                let (network, prefix_len) = parse_network6(&network)?;
+                let mut subnet = Subnet6::new(network, prefix_len);
+
+                parse_block_start(iter, "subnet6")?;
+                parse_scope(&mut subnet.scope, iter)?;
+                parse_block_end(iter)?;
+                scope.subnets6.push(subnet);
+            }
+            "pool6" => {
+                let mut pool = Pool::default();
+
+                parse_block_start(iter, "pool6")?;
+                parse_pool(&mut pool, iter)?;
+                parse_block_end(iter)?;
+                scope.pools6.push(pool);
+            }
+            "range6" => {
+                let words = parse_tokens(iter, "range6")?;
+                let invalid = || ParseError::from(format!("Invalid range6 '{}'", words.join(" ")));
+                let range = match words.as_slice() {
+                    [network, rest @ ..] if network.contains('/') => {
+                        let temporary = match rest {
+                            [] => false,
+                            [t] if t == "temporary" => true,
+                            _ => return Err(invalid()),
+                        };
+                        let (network, prefix_len) = parse_network6(network)?;
+                        let mask = prefix_mask(prefix_len);
+                        let start = u128::from(network) & mask;
+                        Range6 {
+                            start: Ipv6Addr::from(start),
+                            end: Ipv6Addr::from(start | !mask),
+                            temporary,
+                        }
+                    }
+                    [start, end] => Range6 {
+                        start: start.parse().map_err(|_| invalid())?,
+                        end: end.parse().map_err(|_| invalid())?,
+                        temporary: false,
+                    },
+                    _ => return Err(invalid()),
+                };
+                scope.ranges6.push(range);
+            }
+            "prefix6" => {
+                let words = parse_tokens(iter, "prefix6")?;
+                let invalid = || ParseError::from(format!("Invalid prefix6 '{}'", words.join(" ")));
+                if words.len() != 3 || !words[2].starts_with('/') {
+                    return Err(invalid());
+                }
+
+                scope.prefixes6.push(Prefix6 {
+                    start: words[0].parse().map_err(|_| invalid())?,
+                    end: words[1].parse().map_err(|_| invalid())?,
+                    prefix_len: words[2][1..].parse().map_err(|_| invalid())?,
+                });
+            }
+            "if" => {
+                scope.conditionals.push(parse_conditional(iter)?);
+            }
+            "failover" if declares_failover_peer(iter) => {
+                iter.next();
+                iter.next();
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the peer expected"))?
+                    .to_string();
+                let mut peer = FailoverPeer::new(unquote(name));
+
+                parse_block_start(iter, "failover peer")?;
+                parse_failover_peer(&mut peer, iter)?;
+                parse_block_end(iter)?;
+                scope.failover_peers.push(peer);
+            }
+            "key" => {
+                iter.next();
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the key expected"))?
+                    .to_string();
+                let mut key = Key {
+                    name: unquote(name),
+                    algorithm: None,
+                    secret: None,
+                };
+
+                parse_block_start(iter, "key")?;
+                parse_key(&mut key, iter)?;
+                parse_block_end(iter)?;
+                scope.keys.push(key);
+            }
+            "zone" => {
+                iter.next();
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Name of the zone expected"))?
+                    .to_string();
+                let mut zone = Zone {
+                    name: unquote(name),
+                    primary: None,
+                    secondary: None,
+                    key: None,
+                    statements: Vec::new(),
+                };
+
+                parse_block_start(iter, "zone")?;
+                parse_zone(&mut zone, iter)?;
+                parse_block_end(iter)?;
+                scope.zones.push(zone);
+            }
+            "option" => {
+                let mut ahead = iter.clone();
+                let second = ahead.nth(1).map(|t| t.to_string());
+                let third = ahead.next().map(|t| t.to_string());
+
+                if second.as_deref() == Some("space") {
+                    let raw = parse_raw_statement(iter)?;
+                    scope.option_spaces.push(raw.parse()?);
+                } else if third.as_deref() == Some("code") {
+                    let raw = parse_raw_statement(iter)?;
+                    scope.option_definitions.push(raw.parse()?);
+                } else {
+                    iter.next();
+                    let name = iter
+                        .peek()
+                        .ok_or_else(|| ParseError::eof("Option name expected"))?
+                        .to_string();
+                    let value = parse_value(iter, &name)?;
+                    scope.options.insert(name, value);
+                }
+            }
+            _ => {
+                let raw = parse_raw_statement(iter)?;
+                if raw.ends_with('}') {
+                    scope.statements.push(raw);
+                } else {
+                    let (key, value) = match raw.find(' ') {
+                        Some(pos) if &raw[..pos] == "not" => (&raw[pos + 1..], "false"),
+                        Some(pos) => (&raw[..pos], &raw[pos + 1..]),
+                        None => (raw.as_str(), "true"),
+                    };
+                    scope.parameters.insert(key.to_owned(), value.to_owned());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the content of a `host` block, as `parse_scope` does
+fn parse_host<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    host: &mut Host,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        match nc.to_string().as_str() {
+            "}" => {
+                return Ok(());
+            }
+            "hardware" => {
+                host.hardware.replace(parse_hardware(iter)?);
+            }
+            "fixed-address" => {
+                let value = parse_value(iter, "fixed-address")?;
+                host.fixed_addresses = value
+                    .split(',')
+                    .map(|a| a.trim().to_owned())
+                    .filter(|a| !a.is_empty())
+                    .collect();
+            }
+            _ => parse_statement(&mut host.scope, iter)?,
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Parses the content of a `pool` block, as `parse_scope` does
+fn parse_pool<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    pool: &mut Pool,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        match nc.to_string().as_str() {
+            "}" => {
+                return Ok(());
+            }
+            "allow" => {
+                pool.allow.push(parse_value(iter, "allow")?);
+            }
+            "deny" => {
+                pool.deny.push(parse_value(iter, "deny")?);
+            }
+            "failover" => {
+                let value = parse_value(iter, "failover")?;
+                match value.strip_prefix("peer ") {
+                    Some(peer) => pool.failover_peer.replace(unquote(peer.to_owned())),
+                    None => {
+                        return Err(ParseError::unexpected(format!(
+                            "Expected failover peer, found '{}'",
+                            value
+                        )))
+                    }
+                };
+            }
+            _ => parse_statement(&mut pool.scope, iter)?,
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Parses an `if` statement and its `elsif` and `else` blocks. The iterator is
+/// expected to point at the `if` keyword and is left pointing at the closing
+/// brace of the last block.
+fn parse_conditional<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    iter: &mut Peekable<T>,
+) -> Result<Conditional, ParseError> {
+    let mut conditional = Conditional::default();
+
+    loop {
+        let mut condition = Vec::new();
+        loop {
+            iter.next();
+            match iter.peek() {
+                Some(LexItem::Paren('{')) => break,
+                Some(LexItem::Endl) | Some(LexItem::Paren('}')) | None => {
+                    return Err(ParseError::unexpected("Expected '{' after condition"));
+                }
+                Some(t) => condition.push(t.to_string()),
+            }
+        }
+        if condition.is_empty() {
+            return Err(ParseError::unexpected("Condition expected"));
+        }
+
+        let mut branch = Branch {
+            condition,
+            scope: Scope::default(),
+        };
+        iter.next();
+        parse_scope(&mut branch.scope, iter)?;
+        parse_block_end(iter)?;
+        conditional.branches.push(branch);
+
+        let mut ahead = iter.clone();
+        let next = ahead.nth(1).map(|t| t.to_string());
+        let after = ahead.next().map(|t| t.to_string());
+        match (next.as_deref(), after.as_deref()) {
+            (Some("elsif"), _) => {
+                iter.next();
+            }
+            (Some("else"), Some("if")) => {
+                iter.next();
+                iter.next();
+            }
+            (Some("else"), _) => {
+                iter.next();
+                let mut otherwise = Scope::default();
+                parse_block_start(iter, "else")?;
+                parse_scope(&mut otherwise, iter)?;
+                parse_block_end(iter)?;
+                conditional.otherwise.replace(otherwise);
+                return Ok(conditional);
+            }
+            _ => return Ok(conditional),
+        }
+    }
+}
+
+/// Whether the statement is a `failover peer "name" { ... }` declaration
+/// rather than a `failover peer "name";` reference
+fn declares_failover_peer<'l, T: Iterator<Item = &'l LexItem> + Clone>(iter: &Peekable<T>) -> bool {
+    iter.clone().nth(3) == Some(&LexItem::Paren('{'))
+}
+
+/// Parses the content of a `failover peer` block. The iterator is left
+/// pointing at the closing brace.
+fn parse_failover_peer<'l, T: Iterator<Item = &'l LexItem>>(
+    peer: &mut FailoverPeer,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    fn number<N: FromStr>(value: String, name: &str) -> Result<N, String> {
+        value
+            .parse()
+            .map_err(|_| format!("Invalid {} '{}'", name, value))
+    }
+
+    while let Some(&t) = iter.peek() {
+        match t.to_string().as_str() {
+            "}" => return Ok(()),
+            ";" => (),
+            "primary" | "secondary" => {
+                let role = match t.to_string().as_str() {
+                    "primary" => FailoverRole::Primary,
+                    _ => FailoverRole::Secondary,
+                };
+                iter.next();
+                if iter.peek() != Some(&&LexItem::Endl) {
+                    return Err(ParseError::unexpected(format!(
+                        "Expected semicolon after {}",
+                        t
+                    )));
+                }
+                peer.role.replace(role);
+            }
+            "address" => {
+                peer.address.replace(parse_value(iter, "address")?);
+            }
+            "port" => {
+                peer.port
+                    .replace(number(parse_value(iter, "port")?, "port")?);
+            }
+            "peer" => {
+                let value = parse_value(iter, "peer")?;
+                if let Some(address) = value.strip_prefix("address ") {
+                    peer.peer_address.replace(address.to_owned());
+                } else if let Some(port) = value.strip_prefix("port ") {
+                    peer.peer_port
+                        .replace(number(port.to_owned(), "peer port")?);
+                } else {
+                    return Err(ParseError::unexpected(format!(
+                        "Unexpected peer statement '{}'",
+                        value
+                    )));
+                }
+            }
+            "max-response-delay" => {
+                let value = parse_value(iter, "max-response-delay")?;
+                peer.max_response_delay
+                    .replace(number(value, "max-response-delay")?);
+            }
+            "max-unacked-updates" => {
+                let value = parse_value(iter, "max-unacked-updates")?;
+                peer.max_unacked_updates
+                    .replace(number(value, "max-unacked-updates")?);
+            }
+            "mclt" => {
+                peer.mclt
+                    .replace(number(parse_value(iter, "mclt")?, "mclt")?);
+            }
+            "split" => {
+                peer.split
+                    .replace(number(parse_value(iter, "split")?, "split")?);
+            }
+            "hba" => {
+                peer.hba.replace(parse_value(iter, "hba")?);
+            }
+            "load" => {
+                let value = parse_value(iter, "load balance")?;
+                match value.strip_prefix("balance max seconds ") {
+                    Some(seconds) => peer
+                        .load_balance_max_seconds
+                        .replace(number(seconds.to_owned(), "load balance max seconds")?),
+                    None => {
+                        return Err(ParseError::unexpected(format!(
+                            "Unexpected load statement '{}'",
+                            value
+                        )))
+                    }
+                };
+            }
+            _ => peer.statements.push(parse_raw_statement(iter)?),
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Parses the content of a `key` block. The iterator is left pointing at the
+/// closing brace.
+fn parse_key<'l, T: Iterator<Item = &'l LexItem>>(
+    key: &mut Key,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&t) = iter.peek() {
+        match t.to_string().as_str() {
+            "}" => return Ok(()),
+            ";" => (),
+            "algorithm" => {
+                key.algorithm.replace(parse_value(iter, "algorithm")?);
+            }
+            "secret" => {
+                key.secret.replace(unquote(parse_value(iter, "secret")?));
+            }
+            s => {
+                return Err(ParseError::unexpected(format!(
+                    "Unexpected statement '{}' in key",
+                    s
+                )))
+            }
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Parses the content of a `zone` block. The iterator is left pointing at the
+/// closing brace.
+fn parse_zone<'l, T: Iterator<Item = &'l LexItem>>(
+    zone: &mut Zone,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&t) = iter.peek() {
+        match t.to_string().as_str() {
+            "}" => return Ok(()),
+            ";" => (),
+            "primary" => {
+                zone.primary.replace(parse_value(iter, "primary")?);
+            }
+            "secondary" => {
+                zone.secondary.replace(parse_value(iter, "secondary")?);
+            }
+            "key" => {
+                zone.key.replace(unquote(parse_value(iter, "key")?));
+            }
+            _ => zone.statements.push(parse_raw_statement(iter)?),
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Parses the content of a `class` block, as `parse_scope` does
+fn parse_class<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    class: &mut Class,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        match nc.to_string().as_str() {
+            "}" => {
+                return Ok(());
+            }
+            "match" => {
+                let mut tokens = parse_tokens(iter, "match")?;
+                if tokens.first().map(|t| t.as_str()) == Some("if") {
+                    tokens.remove(0);
+                    if tokens.is_empty() {
+                        return Err(ParseError::unexpected("Expression expected after match if"));
+                    }
+                    class.matches.replace(ClassMatch::If(tokens));
+                } else {
+                    class.matches.replace(ClassMatch::Value(tokens));
+                }
+            }
+            "spawn" => {
+                let mut tokens = parse_tokens(iter, "spawn")?;
+                if tokens.len() < 2 || tokens[0] != "with" {
+                    return Err(ParseError::unexpected(
+                        "Expected 'with' and an expression after spawn",
+                    ));
+                }
+                tokens.remove(0);
+                class.spawn_with.replace(tokens);
+            }
+            "lease" => {
+                let value = parse_value(iter, "lease")?;
+                let limit = value
+                    .strip_prefix("limit ")
+                    .and_then(|l| l.parse::<u32>().ok())
+                    .ok_or_else(|| format!("Invalid lease limit '{}'", value))?;
+                class.lease_limit.replace(limit);
+            }
+            _ => parse_statement(&mut class.scope, iter)?,
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Reads the tokens of a statement up to the terminating semicolon. The
+/// iterator is expected to point at the statement keyword and is left
+/// pointing at the semicolon.
+fn parse_tokens<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    name: &str,
+) -> Result<Vec<String>, ParseError> {
+    let mut tokens = Vec::new();
+
+    loop {
+        iter.next();
+        match iter.peek() {
+            Some(LexItem::Endl) => break,
+            Some(LexItem::Paren('{')) | Some(LexItem::Paren('}')) | None => {
+                return Err(ParseError::unexpected(format!(
+                    "Semicolon expected at the end of {}",
+                    name
+                )));
+            }
+            Some(t) => tokens.push(t.to_string()),
+        }
+    }
+
+    if tokens.is_empty() {
+        return Err(ParseError::unexpected(format!(
+            "Expression expected after {}",
+            name
+        )));
+    }
+    Ok(tokens)
+}
+
+/// Parses a `range` statement. The iterator is expected to point at the
+/// `range` keyword and is left pointing at the semicolon.
+fn parse_range<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<Range, ParseError> {
+    let mut dynamic_bootp = false;
+    iter.next();
+    if iter.peek().map(|t| t.to_string()) == Some("dynamic-bootp".to_owned()) {
+        dynamic_bootp = true;
+        iter.next();
+    }
+
+    let token = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof("Range start expected"))?
+        .to_string();
+    let start = token
+        .parse()
+        .map_err(|_| format!("Invalid range start '{}'", token))?;
+    iter.next();
+    let end = match iter.peek() {
+        Some(LexItem::Endl) => start,
+        Some(t) => {
+            let end = t
+                .to_string()
+                .parse()
+                .map_err(|_| format!("Invalid range end '{}'", t))?;
+            iter.next();
+            end
+        }
+        None => return Err(ParseError::eof("Range end or semicolon expected")),
+    };
+
+    match iter.peek() {
+        Some(LexItem::Endl) => Ok(Range {
+            dynamic_bootp,
+            start,
+            end,
+        }),
+        t => Err(ParseError::unexpected(format!(
+            "Expected semicolon, found {:?}",
+            t
+        ))),
+    }
+}
+
+/// Moves to the next token and reads it as an IPv4 address
+fn parse_address<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    what: &str,
+) -> Result<Ipv4Addr, ParseError> {
+    iter.next();
+    let token = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof(format!("{} expected", what)))?
+        .to_string();
+    token
+        .parse()
+        .map_err(|_| ParseError::from(format!("{} '{}' is not a valid IPv4 address", what, token)))
+}
+
+/// Checks that the token after the declaration header opens a block and
+/// moves past it
+fn parse_block_start<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    what: &str,
+) -> Result<(), ParseError> {
+    iter.next();
+    if iter.peek() != Some(&&LexItem::Paren('{')) {
+        return Err(ParseError::unexpected(format!(
+            "Expected '{{' after {} declaration, got '{:?}'",
+            what,
+            iter.peek()
+        )));
+    }
+
+    iter.next();
+    Ok(())
+}
+
+fn parse_block_end<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    match iter.peek() {
+        Some(LexItem::Paren('}')) => Ok(()),
+        t => Err(ParseError::unexpected(format!(
+            "Expected end of section with '}}', got '{:?}'",
+            t
+        ))),
+    }
+}
+
+/// Source of the files referenced by `include` statements
+pub trait FileLoader {
+    /// Returns the content of the file at `path`, as written in the
+    /// `include` statement
+    fn load(&self, path: &str) -> Result<String, ParseError>;
+}
+
+/// Loads included files from the file system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FsLoader;
+
+impl FileLoader for FsLoader {
+    fn load(&self, path: &str) -> Result<String, ParseError> {
+        fs::read_to_string(path).map_err(|source| ParseError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+/// Files kept in memory, keyed by path
+impl FileLoader for HashMap<String, String> {
+    fn load(&self, path: &str) -> Result<String, ParseError> {
+        self.get(path).cloned().ok_or_else(|| ParseError::Io {
+            path: path.into(),
+            source: io::Error::new(io::ErrorKind::NotFound, "not found"),
+        })
+    }
+}
+
+/// Lexes a file, replacing its `include "path";` statements with the tokens
+/// of the included files. `stack` holds the files being included, to detect
+/// cycles.
+fn lex_with_includes<L: FileLoader>(
+    path: &str,
+    loader: &L,
+    stack: &mut Vec<String>,
+) -> Result<Vec<LexItem>, ParseError> {
+    if stack.iter().any(|p| p == path) {
+        return Err(ParseError::unexpected(format!(
+            "Include cycle: {} -> {}",
+            stack.join(" -> "),
+            path
+        )));
+    }
+
+    let tokens = lex(loader.load(path)?)?;
+    stack.push(path.to_owned());
+
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut it = tokens
+        .into_iter()
+        .filter(|t| !matches!(t, LexItem::Comment(_)));
+    while let Some(token) = it.next() {
+        let statement_start = matches!(
+            result.last(),
+            None | Some(LexItem::Endl) | Some(LexItem::Paren('{')) | Some(LexItem::Paren('}'))
+        );
+        if !statement_start || token.to_string() != "include" {
+            result.push(token);
+            continue;
+        }
+
+        let included = match (it.next(), it.next()) {
+            (Some(LexItem::Word(p)), Some(LexItem::Endl)) => unquote(p),
+            _ => {
+                return Err(ParseError::unexpected(format!(
+                    "Invalid include statement in {}",
+                    path
+                )))
+            }
+        };
+        result.extend(lex_with_includes(&included, loader, stack)?);
+    }
+
+    stack.pop();
+    Ok(result)
+}
+
+/// Parses the tokens of a whole configuration. When the position of each
+/// token is known, errors are reported at the token the parser stopped on.
+fn parse_config(
+    tokens: &[LexItem],
+    spans: Option<(&[Span], Span)>,
+) -> Result<DhcpdConfig, ParseError> {
+    let mut config = DhcpdConfig::default();
+    let mut it = tokens.iter().peekable();
+    let result = parse_scope(&mut config.global, &mut it).and_then(|()| match it.peek() {
+        Some(_) => Err(ParseError::unexpected("Unexpected '}' at the top level")),
+        None => Ok(()),
+    });
+
+    match (result, spans) {
+        (Ok(()), _) => Ok(config),
+        (Err(e), None) => Err(e),
+        (Err(e), Some((spans, end))) => {
+            let span = match it.peek() {
+                Some(token) => tokens
+                    .iter()
+                    .position(|t| std::ptr::eq(t, *token))
+                    .map_or(end, |i| spans[i]),
+                None => end,
+            };
+            Err(e.at(span))
+        }
+    }
+}
+
+/// Parses the content of a `dhcpd.conf` file. `include` statements are not
+/// resolved and are kept as parameters, see `parse_with_includes`.
+pub fn parse<S: Into<String>>(input: S) -> Result<DhcpdConfig, ParseError> {
+    let input = input.into();
+    let (tokens, spans): (Vec<LexItem>, Vec<Span>) = lex_spanned(&input)?
+        .into_iter()
+        .filter(|(t, _)| !matches!(t, LexItem::Comment(_)))
+        .unzip();
+
+    parse_config(&tokens, Some((&spans, end_span(&input))))
+}
+
+/// Parses the `dhcpd.conf` file at `path` along with the files it includes,
+/// as if their content was written in place of the `include` statements.
+/// Errors found once the files are put together have no position.
+pub fn parse_with_includes<L: FileLoader>(
+    path: &str,
+    loader: &L,
+) -> Result<DhcpdConfig, ParseError> {
+    let tokens = lex_with_includes(path, loader, &mut Vec::new())?;
+    parse_config(&tokens, None)
+}
@@ -0,0 +1,85 @@
+use std::str::FromStr;
+
+use crate::common::decode_identifier;
+
+/// DHCP unique identifier (RFC 8415, section 11)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Duid {
+    /// Link-layer address plus time (DUID-LLT). `time` is in seconds since
+    /// midnight (UTC), January 1, 2000.
+    Llt {
+        hardware_type: u16,
+        time: u32,
+        link_layer_address: Vec<u8>,
+    },
+    /// Vendor-assigned unique ID based on enterprise number (DUID-EN)
+    En {
+        enterprise_number: u32,
+        identifier: Vec<u8>,
+    },
+    /// Link-layer address (DUID-LL)
+    Ll {
+        hardware_type: u16,
+        link_layer_address: Vec<u8>,
+    },
+    /// Any other DUID type, such as DUID-UUID
+    Other { duid_type: u16, data: Vec<u8> },
+}
+
+impl Duid {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Duid, String> {
+        if bytes.len() < 2 {
+            return Err(format!(
+                "DUID should be at least 2 bytes long, got {}",
+                bytes.len()
+            ));
+        }
+
+        let duid_type = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let data = &bytes[2..];
+        match duid_type {
+            1 => {
+                if data.len() < 6 {
+                    return Err("DUID-LLT is too short".to_owned());
+                }
+                Ok(Duid::Llt {
+                    hardware_type: u16::from_be_bytes([data[0], data[1]]),
+                    time: u32::from_be_bytes([data[2], data[3], data[4], data[5]]),
+                    link_layer_address: data[6..].to_vec(),
+                })
+            }
+            2 => {
+                if data.len() < 4 {
+                    return Err("DUID-EN is too short".to_owned());
+                }
+                Ok(Duid::En {
+                    enterprise_number: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                    identifier: data[4..].to_vec(),
+                })
+            }
+            3 => {
+                if data.len() < 2 {
+                    return Err("DUID-LL is too short".to_owned());
+                }
+                Ok(Duid::Ll {
+                    hardware_type: u16::from_be_bytes([data[0], data[1]]),
+                    link_layer_address: data[2..].to_vec(),
+                })
+            }
+            _ => Ok(Duid::Other {
+                duid_type,
+                data: data.to_vec(),
+            }),
+        }
+    }
+}
+
+/// Parses a DUID written as in the leases file, either as a quoted string with
+/// escapes or as colon separated hexadecimal octets
+impl FromStr for Duid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Duid, String> {
+        Duid::from_bytes(&decode_identifier(s)?)
+    }
+}
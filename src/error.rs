@@ -0,0 +1,142 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::parser::Span;
+
+/// Error returned when a leases file or a date cannot be parsed. Errors
+/// raised while parsing a file carry the position of the offending token.
+#[derive(Debug)]
+pub enum ParseError {
+    /// A token that does not belong at this place, such as a missing
+    /// semicolon or an unknown statement
+    UnexpectedToken { message: String, span: Option<Span> },
+    /// The input ended in the middle of a statement
+    UnexpectedEof { message: String, span: Option<Span> },
+    /// A block or a quoted string that is never closed
+    UnterminatedBlock { message: String, span: Option<Span> },
+    /// A date that cannot be read
+    InvalidDate { message: String, span: Option<Span> },
+    /// Any other value that cannot be read, such as a malformed address
+    InvalidValue { message: String, span: Option<Span> },
+    /// The input goes over one of the `Limits` of the parser options
+    LimitExceeded { message: String, span: Option<Span> },
+    /// The input could not be read
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl ParseError {
+    pub(crate) fn unexpected<S: Into<String>>(message: S) -> ParseError {
+        ParseError::UnexpectedToken {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn eof<S: Into<String>>(expected: S) -> ParseError {
+        ParseError::UnexpectedEof {
+            message: format!("{}, got end of input", expected.into()),
+            span: None,
+        }
+    }
+
+    pub(crate) fn unterminated<S: Into<String>>(message: S) -> ParseError {
+        ParseError::UnterminatedBlock {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn invalid_date<S: Into<String>>(message: S) -> ParseError {
+        ParseError::InvalidDate {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn limit<S: Into<String>>(message: S) -> ParseError {
+        ParseError::LimitExceeded {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// Description of the error, without its position
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::UnexpectedToken { message, .. }
+            | ParseError::UnexpectedEof { message, .. }
+            | ParseError::UnterminatedBlock { message, .. }
+            | ParseError::InvalidDate { message, .. }
+            | ParseError::InvalidValue { message, .. }
+            | ParseError::LimitExceeded { message, .. } => message.clone(),
+            ParseError::Io { path, source } => {
+                format!("Cannot read {}: {}", path.display(), source)
+            }
+        }
+    }
+
+    /// Position of the offending token in the input, if known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::UnterminatedBlock { span, .. }
+            | ParseError::InvalidDate { span, .. }
+            | ParseError::InvalidValue { span, .. }
+            | ParseError::LimitExceeded { span, .. } => *span,
+            ParseError::Io { .. } => None,
+        }
+    }
+
+    /// Sets the position of the error, unless it already has one
+    pub(crate) fn at(mut self, at: Span) -> ParseError {
+        match &mut self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::UnterminatedBlock { span, .. }
+            | ParseError::InvalidDate { span, .. }
+            | ParseError::InvalidValue { span, .. }
+            | ParseError::LimitExceeded { span, .. } => {
+                span.get_or_insert(at);
+            }
+            ParseError::Io { .. } => (),
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.span() {
+            Some(span) => write!(f, "{} at {}", self.message(), span),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Errors of the value parsers, such as invalid hardware addresses
+impl From<String> for ParseError {
+    fn from(message: String) -> ParseError {
+        ParseError::InvalidValue {
+            message,
+            span: None,
+        }
+    }
+}
+
+impl From<&str> for ParseError {
+    fn from(message: &str) -> ParseError {
+        ParseError::from(message.to_owned())
+    }
+}
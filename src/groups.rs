@@ -0,0 +1,54 @@
+use std::iter::Peekable;
+
+use crate::error::ParseError;
+use crate::leases::expect_semicolon;
+use crate::leases::parse_raw_statement;
+use crate::lex::LexItem;
+
+/// Group declaration written to the leases file when a group is created
+/// through OMAPI (`group "name" { dynamic; ... }`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    /// Whether the group was declared with the `subgroup` keyword
+    pub subgroup: bool,
+    pub dynamic: bool,
+    /// Statements of the declaration, kept as raw text
+    pub statements: Vec<String>,
+}
+
+impl Group {
+    pub fn new<S: Into<String>>(name: S) -> Group {
+        Group {
+            name: name.into(),
+            subgroup: false,
+            dynamic: false,
+            statements: Vec::new(),
+        }
+    }
+}
+
+pub fn parse_group<'l, T: Iterator<Item = &'l LexItem>>(
+    group: &mut Group,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        match nc {
+            LexItem::Word(w) if w == "dynamic" => {
+                group.dynamic = true;
+                iter.next();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Endl => (),
+            LexItem::Paren('}') => {
+                return Ok(());
+            }
+            _ => {
+                group.statements.push(parse_raw_statement(iter)?);
+            }
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::iter::Peekable;
+
+use crate::common::Uid;
+use crate::error::ParseError;
+use crate::leases::expect_semicolon;
+use crate::leases::parse_hardware;
+use crate::leases::parse_raw_statement;
+use crate::leases::parse_value;
+use crate::leases::Hardware;
+use crate::leases::LeaseKeyword;
+use crate::lex::LexItem;
+
+/// Host declaration written to the leases file when a host is created
+/// through OMAPI (`host name { dynamic; ... }`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Host {
+    pub name: String,
+    pub dynamic: bool,
+    pub deleted: bool,
+    pub hardware: Option<Hardware>,
+    pub uid: Option<Uid>,
+    pub fixed_address: Option<String>,
+    /// Any other statements of the declaration, kept as raw text
+    pub statements: Vec<String>,
+}
+
+impl Host {
+    pub fn new<S: Into<String>>(name: S) -> Host {
+        Host {
+            name: name.into(),
+            dynamic: false,
+            deleted: false,
+            hardware: None,
+            uid: None,
+            fixed_address: None,
+            statements: Vec::new(),
+        }
+    }
+}
+
+pub fn parse_host<'l, T: Iterator<Item = &'l LexItem>>(
+    host: &mut Host,
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    while let Some(&nc) = iter.peek() {
+        match nc {
+            LexItem::Word(w) if w == "dynamic" => {
+                host.dynamic = true;
+                iter.next();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Opt(LeaseKeyword::Deleted) => {
+                host.deleted = true;
+                iter.next();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Word(w) if w == "fixed-address" => {
+                host.fixed_address
+                    .replace(parse_value(iter, "fixed-address")?);
+            }
+            LexItem::Opt(LeaseKeyword::Hardware) => {
+                host.hardware.replace(parse_hardware(iter)?);
+            }
+            LexItem::Opt(LeaseKeyword::Uid) => {
+                host.uid.replace(parse_value(iter, "uid")?.parse()?);
+            }
+            LexItem::Endl => (),
+            LexItem::Paren('}') => {
+                return Ok(());
+            }
+            _ => {
+                host.statements.push(parse_raw_statement(iter)?);
+            }
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+use std::iter::Peekable;
+
+use crate::common::unescape_bytes;
+use crate::common::Date;
+use crate::duid::Duid;
+use crate::error::ParseError;
+use crate::leases::parse_binding_state;
+use crate::leases::parse_date;
+use crate::leases::parse_end_date;
+use crate::leases::parse_raw_statement;
+use crate::leases::parse_value;
+use crate::leases::BindingState;
+use crate::leases::LeaseKeyword;
+use crate::lex::LexItem;
+use crate::parser::ParseContext;
+use crate::parser::ParseMode;
+use crate::parser::Severity;
+
+/// Type of a DHCPv6 identity association
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IaKind {
+    /// Non-temporary addresses (`ia-na`)
+    Na,
+    /// Temporary addresses (`ia-ta`)
+    Ta,
+    /// Delegated prefixes (`ia-pd`)
+    Pd,
+}
+
+impl fmt::Display for IaKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IaKind::Na => write!(f, "ia-na"),
+            IaKind::Ta => write!(f, "ia-ta"),
+            IaKind::Pd => write!(f, "ia-pd"),
+        }
+    }
+}
+
+/// Identity association from a dhcpd6.leases file
+/// (`ia-na`, `ia-ta` or `ia-pd "<iaid+duid>" { ... }`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ia {
+    pub kind: IaKind,
+    /// IAID followed by the client DUID, as written in the file
+    pub id: String,
+    /// Client last transaction time (`cltt`)
+    pub cltt: Option<Date>,
+    /// Addresses of the `iaaddr` blocks
+    pub addresses: Vec<IaAddr>,
+    /// Delegated prefixes of the `iaprefix` blocks
+    pub prefixes: Vec<IaPrefix>,
+}
+
+/// Address assigned through an `ia-na` or `ia-ta` association
+/// (`iaaddr 2001:db8::1 { ... }`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IaAddr {
+    pub address: String,
+    pub binding_state: Option<BindingState>,
+    /// Preferred lifetime in seconds
+    pub preferred_life: Option<u32>,
+    /// Valid lifetime in seconds
+    pub max_life: Option<u32>,
+    pub ends: Option<Date>,
+    /// Infinite binding, written as `ends never;`
+    pub never_ends: bool,
+}
+
+impl IaAddr {
+    pub fn new<S: Into<String>>(address: S) -> IaAddr {
+        IaAddr {
+            address: address.into(),
+            binding_state: None,
+            preferred_life: None,
+            max_life: None,
+            ends: None,
+            never_ends: false,
+        }
+    }
+}
+
+/// Prefix delegated through an `ia-pd` association
+/// (`iaprefix 2001:db8::/56 { ... }`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IaPrefix {
+    /// Prefix in `address/length` notation
+    pub prefix: String,
+    pub binding_state: Option<BindingState>,
+    /// Preferred lifetime in seconds
+    pub preferred_life: Option<u32>,
+    /// Valid lifetime in seconds
+    pub max_life: Option<u32>,
+    pub ends: Option<Date>,
+    /// Infinite binding, written as `ends never;`
+    pub never_ends: bool,
+}
+
+impl IaPrefix {
+    pub fn new<S: Into<String>>(prefix: S) -> IaPrefix {
+        IaPrefix {
+            prefix: prefix.into(),
+            binding_state: None,
+            preferred_life: None,
+            max_life: None,
+            ends: None,
+            never_ends: false,
+        }
+    }
+}
+
+impl Ia {
+    pub fn new<S: Into<String>>(kind: IaKind, id: S) -> Ia {
+        Ia {
+            kind,
+            id: id.into(),
+            cltt: None,
+            addresses: Vec::new(),
+            prefixes: Vec::new(),
+        }
+    }
+
+    /// Returns the DUID of the client owning the association. The `id`
+    /// starts with the 4 byte IAID, the rest of it is the DUID.
+    pub fn duid(&self) -> Option<Duid> {
+        let id = unescape_bytes(&self.id).ok()?;
+        if id.len() < 4 {
+            return None;
+        }
+
+        Duid::from_bytes(&id[4..]).ok()
+    }
+}
+
+pub fn parse_ia<'l, T: Iterator<Item = &'l LexItem>>(
+    ia: &mut Ia,
+    iter: &mut Peekable<T>,
+    ctx: &mut ParseContext,
+) -> Result<(), ParseError> {
+    let utc_offset = ctx.options.utc_offset;
+
+    while let Some(&nc) = iter.peek() {
+        match nc {
+            LexItem::Word(w) if w == "cltt" => {
+                ia.cltt = Some(parse_date(iter, "cltt date", utc_offset)?);
+            }
+            LexItem::Word(w) if w == "iaaddr" => {
+                iter.next();
+                let address = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Address expected"))?;
+                let mut address = IaAddr::new(address.to_string());
+                iter.next();
+                match iter.peek().ok_or_else(|| ParseError::eof("'{' expected"))? {
+                    LexItem::Paren('{') => (),
+                    s => {
+                        return Err(ParseError::unexpected(format!(
+                            "Expected '{{' after iaaddr, found {}",
+                            s
+                        )))
+                    }
+                }
+                iter.next();
+                parse_binding(&mut address, iter, ctx)?;
+
+                ia.addresses.push(address);
+            }
+            LexItem::Word(w) if w == "iaprefix" => {
+                iter.next();
+                let prefix = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Prefix expected"))?;
+                let mut prefix = IaPrefix::new(prefix.to_string());
+                iter.next();
+                match iter.peek().ok_or_else(|| ParseError::eof("'{' expected"))? {
+                    LexItem::Paren('{') => (),
+                    s => {
+                        return Err(ParseError::unexpected(format!(
+                            "Expected '{{' after iaprefix, found {}",
+                            s
+                        )))
+                    }
+                }
+                iter.next();
+                parse_binding(&mut prefix, iter, ctx)?;
+
+                ia.prefixes.push(prefix);
+            }
+            LexItem::Paren('}') => {
+                return Ok(());
+            }
+            LexItem::Word(_) | LexItem::Opt(_) | LexItem::Decl(_)
+                if ctx.options.mode == ParseMode::Lenient =>
+            {
+                let statement = parse_raw_statement(iter)?;
+                ctx.report(
+                    Severity::Warning,
+                    nc,
+                    format!("Skipped unknown statement '{}' in {}", statement, ia.kind),
+                );
+            }
+            _ => {
+                return Err(ParseError::unexpected(format!(
+                    "Unexpected option '{}'",
+                    nc
+                )));
+            }
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Statements shared by the `iaaddr` and `iaprefix` blocks
+trait IaBinding {
+    /// Block the binding is declared with, for diagnostics
+    fn name(&self) -> String;
+    fn binding_state(&mut self) -> &mut Option<BindingState>;
+    fn preferred_life(&mut self) -> &mut Option<u32>;
+    fn max_life(&mut self) -> &mut Option<u32>;
+    fn ends(&mut self) -> &mut Option<Date>;
+    fn never_ends(&mut self) -> &mut bool;
+}
+
+impl IaBinding for IaAddr {
+    fn name(&self) -> String {
+        format!("iaaddr {}", self.address)
+    }
+
+    fn binding_state(&mut self) -> &mut Option<BindingState> {
+        &mut self.binding_state
+    }
+
+    fn preferred_life(&mut self) -> &mut Option<u32> {
+        &mut self.preferred_life
+    }
+
+    fn max_life(&mut self) -> &mut Option<u32> {
+        &mut self.max_life
+    }
+
+    fn ends(&mut self) -> &mut Option<Date> {
+        &mut self.ends
+    }
+
+    fn never_ends(&mut self) -> &mut bool {
+        &mut self.never_ends
+    }
+}
+
+impl IaBinding for IaPrefix {
+    fn name(&self) -> String {
+        format!("iaprefix {}", self.prefix)
+    }
+
+    fn binding_state(&mut self) -> &mut Option<BindingState> {
+        &mut self.binding_state
+    }
+
+    fn preferred_life(&mut self) -> &mut Option<u32> {
+        &mut self.preferred_life
+    }
+
+    fn max_life(&mut self) -> &mut Option<u32> {
+        &mut self.max_life
+    }
+
+    fn ends(&mut self) -> &mut Option<Date> {
+        &mut self.ends
+    }
+
+    fn never_ends(&mut self) -> &mut bool {
+        &mut self.never_ends
+    }
+}
+
+fn parse_binding<'l, B: IaBinding, T: Iterator<Item = &'l LexItem>>(
+    binding: &mut B,
+    iter: &mut Peekable<T>,
+    ctx: &mut ParseContext,
+) -> Result<(), ParseError> {
+    let utc_offset = ctx.options.utc_offset;
+
+    while let Some(&nc) = iter.peek() {
+        match nc {
+            LexItem::Opt(LeaseKeyword::Binding) => {
+                let (state, _) = parse_binding_state(iter)?;
+                binding.binding_state().replace(state);
+            }
+            LexItem::Word(w) if w == "preferred-life" => {
+                binding
+                    .preferred_life()
+                    .replace(parse_lifetime(iter, "preferred-life")?);
+            }
+            LexItem::Word(w) if w == "max-life" => {
+                binding
+                    .max_life()
+                    .replace(parse_lifetime(iter, "max-life")?);
+            }
+            LexItem::Opt(LeaseKeyword::Ends) => {
+                *binding.ends() = parse_end_date(iter, "end date", utc_offset)?;
+                *binding.never_ends() = binding.ends().is_none();
+            }
+            LexItem::Paren('}') => {
+                return Ok(());
+            }
+            LexItem::Word(_) | LexItem::Opt(_) | LexItem::Decl(_)
+                if ctx.options.mode == ParseMode::Lenient =>
+            {
+                let statement = parse_raw_statement(iter)?;
+                ctx.report(
+                    Severity::Warning,
+                    nc,
+                    format!(
+                        "Skipped unknown statement '{}' in {}",
+                        statement,
+                        binding.name()
+                    ),
+                );
+            }
+            _ => {
+                return Err(ParseError::unexpected(format!(
+                    "Unexpected option '{}'",
+                    nc
+                )));
+            }
+        }
+        iter.next();
+    }
+
+    Err(ParseError::unterminated(
+        "Expected end of section with '}', got end of input",
+    ))
+}
+
+fn parse_lifetime<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    name: &str,
+) -> Result<u32, ParseError> {
+    let value = parse_value(iter, name)?;
+    value
+        .parse::<u32>()
+        .map_err(|_| format!("{} should be a number of seconds. {} is not", name, value).into())
+}
@@ -1,58 +1,312 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FromIterator;
 use std::iter::Peekable;
+use std::net::IpAddr;
 use std::ops::Index;
+use std::str::FromStr;
+use std::time::Duration;
 
+use crate::common::decode_identifier;
+use crate::common::parse_utc_offset;
+use crate::common::unescape_bytes;
 use crate::common::Date;
+use crate::common::DateRange;
+use crate::common::MacAddr;
+use crate::common::Uid;
+use crate::conf::Range;
+use crate::duid::Duid;
+use crate::error::ParseError;
 use crate::lex::LexItem;
+#[cfg(feature = "oui")]
+use crate::oui::OuiDatabase;
+use crate::parser::DuplicatePolicy;
+use crate::parser::ParseContext;
+use crate::parser::ParseMode;
+use crate::parser::ParserOptions;
+use crate::parser::Severity;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LeaseKeyword {
     Abandoned,
+    Atsfp,
+    Billing,
+    Binding,
+    Bootp,
     ClientHostname,
+    Deleted,
     Ends,
     Hardware,
     Hostname,
+    Next,
+    On,
+    Option,
+    Reserved,
+    Rewind,
+    Set,
     Starts,
+    Tstp,
+    Tsfp,
     Uid,
 }
 
-impl LeaseKeyword {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for LeaseKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &LeaseKeyword::Abandoned => "abandoned".to_owned(),
-            &LeaseKeyword::ClientHostname => "client-hostname".to_owned(),
-            &LeaseKeyword::Ends => "ends".to_owned(),
-            &LeaseKeyword::Hardware => "hardware".to_owned(),
-            &LeaseKeyword::Hostname => "hostname".to_owned(),
-            &LeaseKeyword::Starts => "starts".to_owned(),
-            &LeaseKeyword::Uid => "uid".to_owned(),
+            LeaseKeyword::Abandoned => write!(f, "abandoned"),
+            LeaseKeyword::Atsfp => write!(f, "atsfp"),
+            LeaseKeyword::Billing => write!(f, "billing"),
+            LeaseKeyword::Binding => write!(f, "binding"),
+            LeaseKeyword::Bootp => write!(f, "bootp"),
+            LeaseKeyword::ClientHostname => write!(f, "client-hostname"),
+            LeaseKeyword::Deleted => write!(f, "deleted"),
+            LeaseKeyword::Ends => write!(f, "ends"),
+            LeaseKeyword::Hardware => write!(f, "hardware"),
+            LeaseKeyword::Hostname => write!(f, "hostname"),
+            LeaseKeyword::Next => write!(f, "next"),
+            LeaseKeyword::On => write!(f, "on"),
+            LeaseKeyword::Option => write!(f, "option"),
+            LeaseKeyword::Reserved => write!(f, "reserved"),
+            LeaseKeyword::Rewind => write!(f, "rewind"),
+            LeaseKeyword::Set => write!(f, "set"),
+            LeaseKeyword::Starts => write!(f, "starts"),
+            LeaseKeyword::Tstp => write!(f, "tstp"),
+            LeaseKeyword::Tsfp => write!(f, "tsfp"),
+            LeaseKeyword::Uid => write!(f, "uid"),
         }
     }
+}
+
+impl LeaseKeyword {
+    /// Whether the statement sets a single value, so that repeating it
+    /// within a lease conflicts with the previous occurrence
+    fn is_single_valued(&self) -> bool {
+        !matches!(
+            self,
+            LeaseKeyword::Abandoned
+                | LeaseKeyword::Bootp
+                | LeaseKeyword::Deleted
+                | LeaseKeyword::On
+                | LeaseKeyword::Reserved
+        )
+    }
 
     pub fn from(s: &str) -> Result<LeaseKeyword, String> {
         match s {
             "abandoned" => Ok(LeaseKeyword::Abandoned),
+            "atsfp" => Ok(LeaseKeyword::Atsfp),
+            "billing" => Ok(LeaseKeyword::Billing),
+            "binding" => Ok(LeaseKeyword::Binding),
+            "bootp" => Ok(LeaseKeyword::Bootp),
             "client-hostname" => Ok(LeaseKeyword::ClientHostname),
+            "deleted" => Ok(LeaseKeyword::Deleted),
             "ends" => Ok(LeaseKeyword::Ends),
             "hardware" => Ok(LeaseKeyword::Hardware),
             "hostname" => Ok(LeaseKeyword::Hostname),
+            "next" => Ok(LeaseKeyword::Next),
+            "on" => Ok(LeaseKeyword::On),
+            "option" => Ok(LeaseKeyword::Option),
+            "reserved" => Ok(LeaseKeyword::Reserved),
+            "rewind" => Ok(LeaseKeyword::Rewind),
+            "set" => Ok(LeaseKeyword::Set),
             "starts" => Ok(LeaseKeyword::Starts),
+            "tstp" => Ok(LeaseKeyword::Tstp),
+            "tsfp" => Ok(LeaseKeyword::Tsfp),
             "uid" => Ok(LeaseKeyword::Uid),
             _ => Err(format!("'{}' is not a recognized lease option", s)),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LeaseDates {
     pub starts: Option<Date>,
     pub ends: Option<Date>,
+    /// Infinite lease, written as `ends never;`
+    pub never_ends: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl LeaseDates {
+    /// Period during which the lease is valid, unbounded on the sides
+    /// where a date is missing
+    pub fn range(&self) -> DateRange {
+        DateRange::new(self.starts, self.ends)
+    }
+}
+
+/// State of a lease binding (`binding state active;`)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BindingState {
+    Free,
+    Active,
+    Expired,
+    Released,
+    Abandoned,
+    Reset,
+    Backup,
+    Other(String),
+}
+
+impl fmt::Display for BindingState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingState::Free => write!(f, "free"),
+            BindingState::Active => write!(f, "active"),
+            BindingState::Expired => write!(f, "expired"),
+            BindingState::Released => write!(f, "released"),
+            BindingState::Abandoned => write!(f, "abandoned"),
+            BindingState::Reset => write!(f, "reset"),
+            BindingState::Backup => write!(f, "backup"),
+            BindingState::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl TryFrom<&str> for BindingState {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<BindingState, String> {
+        match s {
+            "free" => Ok(BindingState::Free),
+            "active" => Ok(BindingState::Active),
+            "expired" => Ok(BindingState::Expired),
+            "released" => Ok(BindingState::Released),
+            "abandoned" => Ok(BindingState::Abandoned),
+            "reset" => Ok(BindingState::Reset),
+            "backup" => Ok(BindingState::Backup),
+            "" => Err("Binding state should not be empty".to_owned()),
+            _ => Ok(BindingState::Other(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HardwareType {
+    Ethernet,
+    TokenRing,
+    Fddi,
+    Infiniband,
+    Other(String),
+}
+
+impl fmt::Display for HardwareType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HardwareType::Ethernet => write!(f, "ethernet"),
+            HardwareType::TokenRing => write!(f, "token-ring"),
+            HardwareType::Fddi => write!(f, "fddi"),
+            HardwareType::Infiniband => write!(f, "infiniband"),
+            HardwareType::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl FromStr for HardwareType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<HardwareType, String> {
+        match s {
+            "ethernet" => Ok(HardwareType::Ethernet),
+            "token-ring" => Ok(HardwareType::TokenRing),
+            "fddi" => Ok(HardwareType::Fddi),
+            "infiniband" => Ok(HardwareType::Infiniband),
+            "" => Err("Hardware type should not be empty".to_owned()),
+            _ => Ok(HardwareType::Other(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Hardware {
-    pub h_type: String,
-    pub mac: String,
+    pub h_type: HardwareType,
+    /// Hardware address, `None` when dhcpd recorded the type only
+    /// (`hardware ethernet;`)
+    pub mac: Option<MacAddr>,
+}
+
+/// Formats the hardware as in a `hardware` statement, without the keyword
+/// (`ethernet 00:11:22:33:44:55`)
+impl fmt::Display for Hardware {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.mac {
+            Some(mac) => write!(f, "{} {}", self.h_type, mac),
+            None => write!(f, "{}", self.h_type),
+        }
+    }
+}
+
+#[cfg(feature = "oui")]
+impl Hardware {
+    /// Vendor of the hardware address, `None` without an address or when
+    /// its OUI is unknown
+    pub fn vendor<'a>(&self, db: &'a OuiDatabase) -> Option<&'a str> {
+        db.lookup(self.mac.as_ref()?)
+    }
+}
+
+impl FromStr for Hardware {
+    type Err = String;
+
+    /// Parses a hardware type followed by an optional address
+    /// (`ethernet 00:11:22:33:44:55`)
+    fn from_str(s: &str) -> Result<Hardware, String> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let (h_type, mac) = match parts.as_slice() {
+            [h_type] => (h_type, None),
+            [h_type, mac] => (h_type, Some(mac)),
+            _ => return Err(format!("{:?} is not a valid hardware statement", s)),
+        };
+
+        Ok(Hardware {
+            h_type: h_type.parse()?,
+            mac: mac
+                .map(|m| m.parse::<MacAddr>())
+                .transpose()
+                .map_err(|e| format!("Invalid hardware address: {}", e))?,
+        })
+    }
+}
+
+/// Class a lease is billed against (`billing class` or `billing subclass`).
+/// For subclasses `subclass` holds the subclass data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Billing {
+    pub class: String,
+    pub subclass: Option<String>,
+}
+
+/// Statements dhcpd runs when one of `events` happens to a lease
+/// (`on expiry or release { ... }`). Statements are kept as raw text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeaseEvent {
+    pub events: Vec<String>,
+    pub statements: Vec<String>,
+}
+
+/// Dynamic DNS bookkeeping recorded by dhcpd through `set ddns-*` statements
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DdnsInfo {
+    pub fwd_name: Option<String>,
+    pub rev_name: Option<String>,
+    pub txt: Option<String>,
+    pub dhcid: Option<String>,
+    pub client_fqdn: Option<String>,
+}
+
+impl DdnsInfo {
+    fn field_mut(&mut self, variable: &str) -> Option<&mut Option<String>> {
+        match variable {
+            "ddns-fwd-name" => Some(&mut self.fwd_name),
+            "ddns-rev-name" => Some(&mut self.rev_name),
+            "ddns-txt" => Some(&mut self.txt),
+            "ddns-dhcid" => Some(&mut self.dhcid),
+            "ddns-client-fqdn" => Some(&mut self.client_fqdn),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -61,22 +315,103 @@ pub enum LeasesField {
     Hostname,
     LeasedIP,
     MAC,
+    Starts,
+    Ends,
+    BindingState,
+    Uid,
+}
+
+/// Direction of `LeasesMethods::sort_by_field`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
 }
 
+/// Orders present values in the requested direction, missing values last
+fn cmp_missing_last<T: Ord>(a: Option<T>, b: Option<T>, order: SortOrder) -> std::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match order {
+            SortOrder::Ascending => a.cmp(&b),
+            SortOrder::Descending => b.cmp(&a),
+        },
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    }
+}
+
+type ValueGetter = Box<dyn Fn(&Lease) -> Option<String>>;
+
 impl LeasesField {
-    fn value_getter(&self) -> Box<dyn Fn(&Lease) -> Option<String>> {
+    fn value_getter(&self) -> ValueGetter {
         match &self {
             LeasesField::ClientHostname => {
                 Box::new(|l: &Lease| -> Option<String> { l.client_hostname.clone() })
             }
             LeasesField::Hostname => Box::new(|l: &Lease| -> Option<String> { l.hostname.clone() }),
-            LeasesField::LeasedIP => Box::new(|l: &Lease| -> Option<String> { Some(l.ip.clone()) }),
+            LeasesField::LeasedIP => {
+                Box::new(|l: &Lease| -> Option<String> { Some(l.ip.to_string()) })
+            }
             LeasesField::MAC => Box::new(|l: &Lease| -> Option<String> {
-                match &l.hardware {
-                    Some(h) => Some(h.mac.clone()),
+                l.hardware
+                    .as_ref()
+                    .and_then(|h| h.mac.as_ref())
+                    .map(|m| m.to_string())
+            }),
+            LeasesField::Starts => {
+                Box::new(|l: &Lease| -> Option<String> { l.dates.starts.map(|d| d.to_string()) })
+            }
+            LeasesField::Ends => Box::new(|l: &Lease| -> Option<String> {
+                match l.dates.ends {
+                    Some(d) => Some(d.to_string()),
+                    None if l.dates.never_ends => Some("never".to_owned()),
                     None => None,
                 }
             }),
+            LeasesField::BindingState => Box::new(|l: &Lease| -> Option<String> {
+                l.binding_state.as_ref().map(|s| s.to_string())
+            }),
+            LeasesField::Uid => {
+                Box::new(|l: &Lease| -> Option<String> { l.uid.as_ref().map(|u| u.to_string()) })
+            }
+        }
+    }
+
+    /// Compares two leases on the field by its typed value: addresses and
+    /// dates in their natural order, `ends never` after any date
+    fn compare(&self, a: &Lease, b: &Lease, order: SortOrder) -> std::cmp::Ordering {
+        let mac = |l: &Lease| l.hardware.as_ref().and_then(|h| h.mac.clone());
+        let ends = |l: &Lease| match l.dates.ends {
+            Some(d) => Some((false, Some(d))),
+            None if l.dates.never_ends => Some((true, None)),
+            None => None,
+        };
+
+        match self {
+            LeasesField::ClientHostname => cmp_missing_last(
+                a.client_hostname.as_ref(),
+                b.client_hostname.as_ref(),
+                order,
+            ),
+            LeasesField::Hostname => {
+                cmp_missing_last(a.hostname.as_ref(), b.hostname.as_ref(), order)
+            }
+            LeasesField::LeasedIP => cmp_missing_last(Some(a.ip), Some(b.ip), order),
+            LeasesField::MAC => cmp_missing_last(mac(a), mac(b), order),
+            LeasesField::Starts => cmp_missing_last(a.dates.starts, b.dates.starts, order),
+            LeasesField::Ends => cmp_missing_last(ends(a), ends(b), order),
+            LeasesField::BindingState => cmp_missing_last(
+                a.binding_state.as_ref().map(|s| s.to_string()),
+                b.binding_state.as_ref().map(|s| s.to_string()),
+                order,
+            ),
+            LeasesField::Uid => cmp_missing_last(
+                a.uid.as_ref().map(|u| u.as_bytes()),
+                b.uid.as_ref().map(|u| u.as_bytes()),
+                order,
+            ),
         }
     }
 }
@@ -92,10 +427,182 @@ impl Index<usize> for Leases {
     }
 }
 
+impl IntoIterator for Leases {
+    type Item = Lease;
+    type IntoIter = std::vec::IntoIter<Lease>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Leases {
+    type Item = &'a Lease;
+    type IntoIter = std::slice::Iter<'a, Lease>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Leases {
+    type Item = &'a mut Lease;
+    type IntoIter = std::slice::IterMut<'a, Lease>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+impl FromIterator<Lease> for Leases {
+    fn from_iter<I: IntoIterator<Item = Lease>>(iter: I) -> Leases {
+        Leases(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Lease> for Leases {
+    fn extend<I: IntoIterator<Item = Lease>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+/// The last lease of each address, in the order of these last occurrences
+fn latest_by_ip(leases: &[Lease]) -> Vec<&Lease> {
+    let last: HashMap<IpAddr, usize> = leases.iter().enumerate().map(|(i, l)| (l.ip, i)).collect();
+
+    leases
+        .iter()
+        .enumerate()
+        .filter(|(i, l)| last[&l.ip] == *i)
+        .map(|(_, l)| l)
+        .collect()
+}
+
+/// A lease of the same address and client in both snapshots
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeaseChange<'a> {
+    pub before: &'a Lease,
+    pub after: &'a Lease,
+}
+
+/// Differences between two snapshots of a leases file, see
+/// `LeasesMethods::diff`. A lease whose address was handed to another
+/// hardware address counts as removed and added.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeaseDiff<'a> {
+    /// Addresses only leased in the later snapshot
+    pub added: Vec<&'a Lease>,
+    /// Addresses no longer leased in the later snapshot
+    pub removed: Vec<&'a Lease>,
+    /// Leases whose dates changed
+    pub renewed: Vec<LeaseChange<'a>>,
+    /// Leases whose binding state changed, they may be renewed as well
+    pub state_changed: Vec<LeaseChange<'a>>,
+}
+
+impl LeaseDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renewed.is_empty()
+            && self.state_changed.is_empty()
+    }
+}
+
+/// Statistics over a lease collection, see `LeasesMethods::summary`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeasesSummary {
+    /// Number of lease blocks, including repeated addresses
+    pub total: usize,
+    pub by_binding_state: HashMap<BindingState, usize>,
+    /// Leases without a `binding state`, as written by OpenBSD dhcpd
+    pub without_binding_state: usize,
+    /// Leases flagged `abandoned` or in the abandoned binding state
+    pub abandoned: usize,
+    pub unique_macs: usize,
+    pub unique_ips: usize,
+    pub earliest_start: Option<Date>,
+    pub latest_end: Option<Date>,
+    /// Average time between start and end of the leases having both
+    pub average_duration: Option<Duration>,
+}
+
+/// Whether two leases belong to different clients, identified by their
+/// hardware address or else by their uid. Leases lacking a common identifier
+/// are not told apart.
+fn different_clients(a: &Lease, b: &Lease) -> bool {
+    let a_mac = a.hardware.as_ref().and_then(|h| h.mac.as_ref());
+    let b_mac = b.hardware.as_ref().and_then(|h| h.mac.as_ref());
+
+    match (a_mac, b_mac) {
+        (Some(m), Some(n)) => m != n,
+        _ => match (&a.uid, &b.uid) {
+            (Some(u), Some(v)) => u != v,
+            _ => false,
+        },
+    }
+}
+
+/// An address leased to several clients over overlapping periods, with the
+/// offending leases in file order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IpConflict<'a> {
+    pub ip: IpAddr,
+    pub leases: Vec<&'a Lease>,
+}
+
+/// Address usage of a single range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeUtilization {
+    pub range: Range,
+    /// Number of addresses in the range
+    pub size: u64,
+    /// Addresses with an active lease
+    pub used: u64,
+    /// Addresses whose lease is abandoned, they are not handed out again
+    /// until reclaimed
+    pub abandoned: u64,
+    /// Addresses neither used nor abandoned
+    pub free: u64,
+}
+
+impl RangeUtilization {
+    /// Share of the range in use, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        if self.size == 0 {
+            return 0.0;
+        }
+        self.used as f64 / self.size as f64
+    }
+}
+
+/// Address usage per range, see `LeasesMethods::utilization`. The totals
+/// sum up the ranges, addresses of overlapping ranges count once per range.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Utilization {
+    pub ranges: Vec<RangeUtilization>,
+    pub size: u64,
+    pub used: u64,
+    pub abandoned: u64,
+    pub free: u64,
+}
+
+/// How leases found in both collections are handled by `LeasesMethods::merge`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeDuplicates {
+    /// Every lease is kept
+    #[default]
+    KeepAll,
+    /// Identical lease blocks are kept once, at their first position
+    Identical,
+    /// Only the last lease of each address is kept
+    LatestPerIp,
+}
+
 pub trait LeasesMethods {
     fn all(&self) -> Vec<Lease>;
 
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "any filtering logic should be done by user")]
     fn active_by<S: AsRef<str>>(
         &self,
         field_name: LeasesField,
@@ -103,34 +610,126 @@ pub trait LeasesMethods {
         active_at: Date,
     ) -> Option<Lease>;
 
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "use `find_last_by` instead")]
     fn by_leased<S: AsRef<str>>(&self, ip: S) -> Option<Lease>;
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "use `filter_by` instead")]
     fn by_leased_all<S: AsRef<str>>(&self, ip: S) -> Vec<Lease>;
 
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "use `find_last_by` instead")]
     fn by_mac<S: AsRef<str>>(&self, mac: S) -> Option<Lease>;
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "use `filter_by` instead")]
     fn by_mac_all<S: AsRef<str>>(&self, mac: S) -> Vec<Lease>;
 
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "any filtering logic should be done by user")]
     fn active_by_hostname<S: AsRef<str>>(&self, hostname: S, active_at: Date) -> Option<Lease>;
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "use `filter_by` instead")]
     fn by_hostname_all<S: AsRef<str>>(&self, hostname: S) -> Vec<Lease>;
 
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "any filtering logic should be done by user")]
     fn active_by_client_hostname<S: AsRef<str>>(
         &self,
         hostname: S,
         active_at: Date,
     ) -> Option<Lease>;
-    #[deprecated(since = "0.4.3", note="any filtering logic should be done by user")]
+    #[deprecated(since = "0.4.3", note = "use `filter_by` instead")]
     fn by_client_hostname_all<S: AsRef<str>>(&self, hostname: S) -> Vec<Lease>;
 
-    fn new() -> Leases;
+    fn new() -> Self;
     fn push(&mut self, l: Lease);
+    /// Iterates over the leases in file order, without copying them
+    fn iter(&self) -> std::slice::Iter<'_, Lease>;
+    fn iter_mut(&mut self) -> std::slice::IterMut<'_, Lease>;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn get(&self, i: usize) -> Option<&Lease>;
+    fn first(&self) -> Option<&Lease>;
+    fn last(&self) -> Option<&Lease>;
+    /// Keeps only the leases matching the predicate, in their order
+    fn retain<F: FnMut(&Lease) -> bool>(&mut self, f: F);
+    /// Copies of the leases matching a predicate, in file order
+    fn filter_by<F: FnMut(&Lease) -> bool>(&self, f: F) -> Leases;
+    /// Last lease in the file matching a predicate, which is the current one
+    /// for an address or a client
+    fn find_last_by<F: FnMut(&Lease) -> bool>(&self, f: F) -> Option<&Lease>;
+    /// Stable sort, leases comparing equal keep their file order
+    fn sort_by<F: FnMut(&Lease, &Lease) -> std::cmp::Ordering>(&mut self, compare: F);
+    /// Stable sort by a key, such as `|l| l.dates.starts`
+    fn sort_by_key<K: Ord, F: FnMut(&Lease) -> K>(&mut self, f: F);
+    /// Stable sort on a field, leases missing the field come last in both
+    /// directions
+    fn sort_by_field(&mut self, field: LeasesField, order: SortOrder);
+    /// Removes the lease at the given position, `None` if it is out of bounds
+    fn remove(&mut self, i: usize) -> Option<Lease>;
+    /// Merges the leases of another file, such as the current file into its
+    /// rotated predecessor. Both are expected in file order, leases are
+    /// interleaved by start date and on equal or missing dates those of
+    /// `self` come first.
+    fn merge(&mut self, other: Leases, duplicates: MergeDuplicates);
+    /// Changes from this snapshot to a later one, comparing the latest lease
+    /// of each address. See `LeaseDiff`.
+    fn diff<'a>(&'a self, other: &'a Leases) -> LeaseDiff<'a>;
+    /// The last lease written for each address. Lease files are append only,
+    /// so the last block of an address is its current state.
+    fn latest_per_ip(&self) -> HashMap<IpAddr, Lease>;
+    /// The last lease written for each hardware address, which holds the
+    /// address the device currently has. Leases without a hardware address
+    /// are skipped.
+    fn latest_per_mac(&self) -> HashMap<MacAddr, Lease>;
+    /// Leases active at the given date and not abandoned, in file order.
+    /// Replaces the `active_by*` lookups, further filtering is left to the
+    /// caller.
+    fn active_at<D: Into<Date>>(&self, when: D) -> impl Iterator<Item = &Lease>;
+    /// Starts a query over the leases, see `LeaseQuery`
+    fn query(&self) -> LeaseQuery<'_>;
+    /// Leases ordered by start date, for fast time window lookups
+    fn sorted_by_start(&self) -> SortedLeases<'_>;
+    /// Latest lease of each address whose end falls between `from` and
+    /// `from + window`, both included, in file order
+    fn expiring_within<D: Into<Date>>(&self, from: D, window: Duration) -> Vec<&Lease>;
+    /// Latest lease of each address when it ended before the cutoff, in
+    /// file order. Leases without an end date never expire.
+    fn expired_before<D: Into<Date>>(&self, cutoff: D) -> Vec<&Lease>;
+    /// Removes every lease of the addresses whose latest lease ended before
+    /// the cutoff, and returns them in file order
+    fn remove_expired_before<D: Into<Date>>(&mut self, cutoff: D) -> Leases;
+    /// Groups the leases by the key computed for each of them, keeping the
+    /// file order within groups
+    fn group_by<K: Eq + Hash, F: FnMut(&Lease) -> K>(&self, key: F) -> HashMap<K, Vec<&Lease>>;
+    /// Groups the leases by the vendor of their hardware address, leases
+    /// without a known vendor are grouped under `None`
+    #[cfg(feature = "oui")]
+    fn group_by_vendor<'a>(
+        &'a self,
+        db: &'a OuiDatabase,
+    ) -> HashMap<Option<&'a str>, Vec<&'a Lease>>;
+    /// All leases of each address in file order, for repeated lookups
+    fn index_by_ip(&self) -> HashMap<IpAddr, Vec<&Lease>>;
+    /// All leases of each hardware address in file order, for repeated
+    /// lookups. Leases without a hardware address are skipped.
+    fn index_by_mac(&self) -> HashMap<&MacAddr, Vec<&Lease>>;
     fn hostnames(&self) -> HashSet<String>;
     fn client_hostnames(&self) -> HashSet<String>;
+    /// Distinct hardware addresses of the leases
+    fn macs(&self) -> HashSet<MacAddr>;
+    /// Distinct leased addresses
+    fn ips(&self) -> HashSet<IpAddr>;
+    /// Overview of the whole collection, see `LeasesSummary`
+    fn summary(&self) -> LeasesSummary;
+    /// Number of leases active and not abandoned at `start`, then every
+    /// `step` up to `end` included, as `active_at` would count them. A zero
+    /// step samples `start` only.
+    fn occupancy_series<D: Into<Date>>(
+        &self,
+        start: D,
+        end: D,
+        step: Duration,
+    ) -> Vec<(Date, usize)>;
+    /// Addresses held at the same time by different clients, see
+    /// `IpConflict`. Conflicts are listed in file order.
+    fn conflicts(&self) -> Vec<IpConflict<'_>>;
+    /// Usage of each address range at the given date, based on the latest
+    /// lease of every address. See `Utilization`.
+    fn utilization<D: Into<Date>>(&self, ranges: &[Range], at: D) -> Utilization;
 }
 
 impl LeasesMethods for Leases {
@@ -151,7 +750,15 @@ impl LeasesMethods for Leases {
         value: S,
         active_at: Date,
     ) -> Option<Lease> {
-        let expected_val = value.as_ref();
+        // MACs are compared in their normalized form
+        let expected_val = match field {
+            LeasesField::MAC => value
+                .as_ref()
+                .parse::<MacAddr>()
+                .map(|m| m.to_string())
+                .unwrap_or_else(|_| value.as_ref().to_owned()),
+            _ => value.as_ref().to_owned(),
+        };
         let get_val = field.value_getter();
 
         let mut ls = self.0.clone();
@@ -173,13 +780,7 @@ impl LeasesMethods for Leases {
         let mut ls = self.0.clone();
         ls.reverse();
 
-        for l in ls {
-            if l.ip == ip.as_ref() {
-                return Some(l);
-            }
-        }
-
-        None
+        ls.into_iter().find(|l| l.ip.to_string() == ip.as_ref())
     }
 
     fn by_leased_all<S: AsRef<str>>(&self, ip: S) -> Vec<Lease> {
@@ -187,12 +788,12 @@ impl LeasesMethods for Leases {
         let ls = self.0.clone();
 
         for l in ls {
-            if l.ip == ip.as_ref() {
+            if l.ip.to_string() == ip.as_ref() {
                 result.push(l);
             }
         }
 
-        return result;
+        result
     }
 
     fn by_mac<S: AsRef<str>>(&self, mac: S) -> Option<Lease> {
@@ -201,7 +802,7 @@ impl LeasesMethods for Leases {
 
         for l in ls {
             let hw = l.hardware.as_ref();
-            if hw.is_some() && hw.unwrap().mac == mac.as_ref() {
+            if hw.is_some() && hw.unwrap().mac.as_ref().is_some_and(|m| m == mac.as_ref()) {
                 return Some(l);
             }
         }
@@ -215,12 +816,12 @@ impl LeasesMethods for Leases {
 
         for l in ls {
             let hw = l.hardware.as_ref();
-            if hw.is_some() && hw.unwrap().mac == mac.as_ref() {
+            if hw.is_some() && hw.unwrap().mac.as_ref().is_some_and(|m| m == mac.as_ref()) {
                 result.push(l);
             }
         }
 
-        return result;
+        result
     }
 
     fn active_by_hostname<S: AsRef<str>>(&self, hostname: S, active_at: Date) -> Option<Lease> {
@@ -267,7 +868,7 @@ impl LeasesMethods for Leases {
         res
     }
 
-    fn new() -> Leases {
+    fn new() -> Self {
         Leases(Vec::new())
     }
 
@@ -275,17 +876,243 @@ impl LeasesMethods for Leases {
         self.0.push(l);
     }
 
+    fn iter(&self) -> std::slice::Iter<'_, Lease> {
+        self.0.iter()
+    }
+
+    fn iter_mut(&mut self) -> std::slice::IterMut<'_, Lease> {
+        self.0.iter_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn get(&self, i: usize) -> Option<&Lease> {
+        self.0.get(i)
+    }
+
+    fn first(&self) -> Option<&Lease> {
+        self.0.first()
+    }
+
+    fn last(&self) -> Option<&Lease> {
+        self.0.last()
+    }
+
+    fn retain<F: FnMut(&Lease) -> bool>(&mut self, f: F) {
+        self.0.retain(f)
+    }
+
+    fn filter_by<F: FnMut(&Lease) -> bool>(&self, mut f: F) -> Leases {
+        self.0.iter().filter(|l| f(l)).cloned().collect()
+    }
+
+    fn find_last_by<F: FnMut(&Lease) -> bool>(&self, mut f: F) -> Option<&Lease> {
+        self.0.iter().rev().find(|l| f(l))
+    }
+
+    fn sort_by<F: FnMut(&Lease, &Lease) -> std::cmp::Ordering>(&mut self, compare: F) {
+        self.0.sort_by(compare)
+    }
+
+    fn sort_by_key<K: Ord, F: FnMut(&Lease) -> K>(&mut self, f: F) {
+        self.0.sort_by_key(f)
+    }
+
+    fn sort_by_field(&mut self, field: LeasesField, order: SortOrder) {
+        self.0.sort_by(|a, b| field.compare(a, b, order))
+    }
+
+    fn remove(&mut self, i: usize) -> Option<Lease> {
+        if i < self.0.len() {
+            Some(self.0.remove(i))
+        } else {
+            None
+        }
+    }
+
+    fn diff<'a>(&'a self, other: &'a Leases) -> LeaseDiff<'a> {
+        let before = latest_by_ip(&self.0);
+        let after = latest_by_ip(&other.0);
+        let before_ips: HashMap<IpAddr, &Lease> = before.iter().map(|l| (l.ip, *l)).collect();
+        let after_ips: HashMap<IpAddr, &Lease> = after.iter().map(|l| (l.ip, *l)).collect();
+        let client = |l: &Lease| l.hardware.as_ref().and_then(|h| h.mac.clone());
+
+        let mut diff = LeaseDiff::default();
+        for old in &before {
+            match after_ips.get(&old.ip) {
+                Some(new) if client(old) == client(new) => {
+                    let change = LeaseChange {
+                        before: old,
+                        after: new,
+                    };
+                    if old.dates != new.dates {
+                        diff.renewed.push(change.clone());
+                    }
+                    if old.binding_state != new.binding_state {
+                        diff.state_changed.push(change);
+                    }
+                }
+                _ => diff.removed.push(old),
+            }
+        }
+        for new in &after {
+            match before_ips.get(&new.ip) {
+                Some(old) if client(old) == client(new) => (),
+                _ => diff.added.push(new),
+            }
+        }
+
+        diff
+    }
+
+    fn merge(&mut self, other: Leases, duplicates: MergeDuplicates) {
+        let mut ours = std::mem::take(&mut self.0).into_iter().peekable();
+        let mut theirs = other.0.into_iter().peekable();
+        let mut merged = Vec::with_capacity(ours.len() + theirs.len());
+
+        loop {
+            let take_theirs = match (ours.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => match (a.dates.starts, b.dates.starts) {
+                    (Some(a), Some(b)) => b < a,
+                    _ => false,
+                },
+                (None, Some(_)) => true,
+                (_, None) => false,
+            };
+            let next = if take_theirs {
+                theirs.next()
+            } else {
+                ours.next()
+            };
+            match next {
+                Some(l) => merged.push(l),
+                None => break,
+            }
+        }
+
+        self.0 = match duplicates {
+            MergeDuplicates::KeepAll => merged,
+            MergeDuplicates::Identical => {
+                let mut seen: HashSet<&Lease> = HashSet::with_capacity(merged.len());
+                let first: Vec<bool> = merged.iter().map(|l| seen.insert(l)).collect();
+                merged
+                    .into_iter()
+                    .zip(first)
+                    .filter_map(|(l, first)| first.then_some(l))
+                    .collect()
+            }
+            MergeDuplicates::LatestPerIp => latest_by_ip(&merged).into_iter().cloned().collect(),
+        };
+    }
+
+    fn latest_per_ip(&self) -> HashMap<IpAddr, Lease> {
+        self.0.iter().map(|l| (l.ip, l.clone())).collect()
+    }
+
+    fn active_at<D: Into<Date>>(&self, when: D) -> impl Iterator<Item = &Lease> {
+        let when = when.into();
+        self.0
+            .iter()
+            .filter(move |l| l.is_active_at(when) && !l.abandoned)
+    }
+
+    fn query(&self) -> LeaseQuery<'_> {
+        LeaseQuery::new(&self.0)
+    }
+
+    fn sorted_by_start(&self) -> SortedLeases<'_> {
+        SortedLeases::new(&self.0)
+    }
+
+    fn expired_before<D: Into<Date>>(&self, cutoff: D) -> Vec<&Lease> {
+        let cutoff = cutoff.into();
+        latest_by_ip(&self.0)
+            .into_iter()
+            .filter(|l| l.dates.ends.is_some_and(|ends| ends < cutoff))
+            .collect()
+    }
+
+    fn remove_expired_before<D: Into<Date>>(&mut self, cutoff: D) -> Leases {
+        let expired: HashSet<IpAddr> = self.expired_before(cutoff).iter().map(|l| l.ip).collect();
+        let (removed, kept) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|l| expired.contains(&l.ip));
+        self.0 = kept;
+
+        Leases(removed)
+    }
+
+    fn expiring_within<D: Into<Date>>(&self, from: D, window: Duration) -> Vec<&Lease> {
+        let from = from.into();
+        let secs = i64::try_from(window.as_secs()).unwrap_or(i64::MAX);
+        let until = from
+            .to_unix_timestamp()
+            .map(|ts| Date::from_unix_timestamp(ts.saturating_add(secs)));
+        let range = DateRange::new(Some(from), until);
+
+        latest_by_ip(&self.0)
+            .into_iter()
+            .filter(|l| l.dates.ends.is_some_and(|ends| range.contains(&ends)))
+            .collect()
+    }
+
+    fn group_by<K: Eq + Hash, F: FnMut(&Lease) -> K>(&self, mut key: F) -> HashMap<K, Vec<&Lease>> {
+        let mut groups: HashMap<K, Vec<&Lease>> = HashMap::new();
+        for l in &self.0 {
+            groups.entry(key(l)).or_default().push(l);
+        }
+        groups
+    }
+
+    #[cfg(feature = "oui")]
+    fn group_by_vendor<'a>(
+        &'a self,
+        db: &'a OuiDatabase,
+    ) -> HashMap<Option<&'a str>, Vec<&'a Lease>> {
+        self.group_by(|l| l.hardware.as_ref().and_then(|h| h.vendor(db)))
+    }
+
+    fn index_by_ip(&self) -> HashMap<IpAddr, Vec<&Lease>> {
+        self.group_by(|l| l.ip)
+    }
+
+    fn index_by_mac(&self) -> HashMap<&MacAddr, Vec<&Lease>> {
+        let mut index: HashMap<&MacAddr, Vec<&Lease>> = HashMap::new();
+        for l in &self.0 {
+            if let Some(mac) = l.hardware.as_ref().and_then(|h| h.mac.as_ref()) {
+                index.entry(mac).or_default().push(l);
+            }
+        }
+        index
+    }
+
+    fn latest_per_mac(&self) -> HashMap<MacAddr, Lease> {
+        self.0
+            .iter()
+            .filter_map(|l| {
+                let mac = l.hardware.as_ref()?.mac.as_ref()?;
+                Some((mac.clone(), l.clone()))
+            })
+            .collect()
+    }
+
     fn hostnames(&self) -> HashSet<String> {
         let mut res = HashSet::new();
         let ls = self.0.clone();
 
         for l in ls {
-            if l.hostname.is_some() {
-                res.insert(l.hostname.unwrap());
+            if let Some(hostname) = l.hostname {
+                res.insert(hostname);
             }
         }
 
-        return res;
+        res
     }
 
     fn client_hostnames(&self) -> HashSet<String> {
@@ -293,195 +1120,1313 @@ impl LeasesMethods for Leases {
         let ls = self.0.clone();
 
         for l in ls {
-            if l.client_hostname.is_some() {
-                res.insert(l.client_hostname.unwrap());
+            if let Some(client_hostname) = l.client_hostname {
+                res.insert(client_hostname);
             }
         }
 
-        return res;
+        res
     }
-}
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Lease {
-    pub ip: String,
-    pub dates: LeaseDates,
-    pub hardware: Option<Hardware>,
-    pub uid: Option<String>,
-    pub client_hostname: Option<String>,
-    pub hostname: Option<String>,
-    pub abandoned: bool,
-}
+    fn macs(&self) -> HashSet<MacAddr> {
+        self.0
+            .iter()
+            .filter_map(|l| l.hardware.as_ref()?.mac.clone())
+            .collect()
+    }
 
-impl Lease {
-    pub fn new() -> Lease {
-        Lease {
-            ip: "localhost".to_owned(),
-            dates: LeaseDates {
-                starts: None,
-                ends: None,
-            },
-            hardware: None,
-            uid: None,
-            client_hostname: None,
-            hostname: None,
-            abandoned: false,
+    fn ips(&self) -> HashSet<IpAddr> {
+        self.0.iter().map(|l| l.ip).collect()
+    }
+
+    fn utilization<D: Into<Date>>(&self, ranges: &[Range], at: D) -> Utilization {
+        let at = at.into();
+        let latest = latest_by_ip(&self.0);
+        let mut utilization = Utilization::default();
+
+        for range in ranges {
+            let mut usage = RangeUtilization {
+                range: range.clone(),
+                size: range.size(),
+                used: 0,
+                abandoned: 0,
+                free: 0,
+            };
+
+            for l in &latest {
+                let in_range = match l.ip {
+                    IpAddr::V4(ip) => range.contains(ip),
+                    IpAddr::V6(_) => false,
+                };
+                if !in_range {
+                    continue;
+                }
+
+                if l.abandoned || l.binding_state == Some(BindingState::Abandoned) {
+                    usage.abandoned += 1;
+                } else if l.is_active_at(at)
+                    && matches!(l.binding_state, None | Some(BindingState::Active))
+                {
+                    usage.used += 1;
+                }
+            }
+            usage.free = usage.size.saturating_sub(usage.used + usage.abandoned);
+
+            utilization.size += usage.size;
+            utilization.used += usage.used;
+            utilization.abandoned += usage.abandoned;
+            utilization.free += usage.free;
+            utilization.ranges.push(usage);
         }
+
+        utilization
     }
 
-    pub fn is_active_at(&self, when: Date) -> bool {
-        if self.dates.starts.is_some() && self.dates.starts.unwrap() > when {
-            return false;
+    fn conflicts(&self) -> Vec<IpConflict<'_>> {
+        // leases holding their address, by address in order of appearance
+        let mut order = Vec::new();
+        let mut by_ip: HashMap<IpAddr, Vec<&Lease>> = HashMap::new();
+        for l in &self.0 {
+            let holding =
+                !l.abandoned && matches!(l.binding_state, None | Some(BindingState::Active));
+            if holding {
+                by_ip
+                    .entry(l.ip)
+                    .or_insert_with(|| {
+                        order.push(l.ip);
+                        Vec::new()
+                    })
+                    .push(l);
+            }
         }
 
-        if self.dates.ends.is_some() && self.dates.ends.unwrap() < when {
-            return false;
+        let mut conflicts = Vec::new();
+        for ip in order {
+            let leases = &by_ip[&ip];
+            let mut involved = vec![false; leases.len()];
+
+            for (i, a) in leases.iter().enumerate() {
+                for (j, b) in leases.iter().enumerate().skip(i + 1) {
+                    if different_clients(a, b) && a.dates.range().overlaps(&b.dates.range()) {
+                        involved[i] = true;
+                        involved[j] = true;
+                    }
+                }
+            }
+
+            if involved.contains(&true) {
+                conflicts.push(IpConflict {
+                    ip,
+                    leases: leases
+                        .iter()
+                        .zip(involved)
+                        .filter(|(_, involved)| *involved)
+                        .map(|(l, _)| *l)
+                        .collect(),
+                });
+            }
         }
 
-        return true;
+        conflicts
     }
-}
 
-pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
-    lease: &mut Lease,
-    iter: &mut Peekable<T>,
-) -> Result<(), String> {
-    while let Some(&nc) = iter.peek() {
-        match nc {
-            LexItem::Opt(LeaseKeyword::Starts) => {
-                iter.next();
-                let weekday = iter
-                    .peek()
-                    .expect("Weekday for start date expected")
-                    .to_string();
-                iter.next();
-                let date = iter
-                    .peek()
-                    .expect("Date for start date expected")
-                    .to_string();
-                iter.next();
-                let time = iter
-                    .peek()
-                    .expect("Time for start date expected")
-                    .to_string();
-                iter.next();
+    fn occupancy_series<D: Into<Date>>(
+        &self,
+        start: D,
+        end: D,
+        step: Duration,
+    ) -> Vec<(Date, usize)> {
+        let (start, end) = match (
+            start.into().to_unix_timestamp(),
+            end.into().to_unix_timestamp(),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Vec::new(),
+        };
+        let step = i64::try_from(step.as_secs()).unwrap_or(i64::MAX);
+
+        // a lease counts from its start to its end, both included
+        let mut events = Vec::new();
+        for l in self.0.iter().filter(|l| !l.abandoned) {
+            // leases with dates out of the timestamp range are left out
+            let starts = match l.dates.starts.map(|d| d.to_unix_timestamp()) {
+                Some(Some(starts)) => starts,
+                Some(None) => continue,
+                None => i64::MIN,
+            };
+            let ends = match l.dates.ends.map(|d| d.to_unix_timestamp()) {
+                Some(Some(ends)) => Some(ends),
+                Some(None) => continue,
+                None => None,
+            };
+            if ends.is_some_and(|ends| ends < starts) {
+                continue;
+            }
+            events.push((starts, 1));
+            if let Some(ends) = ends {
+                events.push((ends.saturating_add(1), -1));
+            }
+        }
+        events.sort_unstable();
+
+        let mut series = Vec::new();
+        let mut events = events.into_iter().peekable();
+        let mut active: i64 = 0;
+        let mut t = start;
+        while t <= end {
+            while let Some((_, delta)) = events.next_if(|(at, _)| *at <= t) {
+                active += delta;
+            }
+            series.push((Date::from_unix_timestamp(t), active as usize));
 
-                let tz = iter
-                    .peek()
-                    .expect("Timezone or semicolon expected")
-                    .to_string();
-                if tz != LexItem::Endl.to_string() {
-                    iter.next();
-                    match iter.peek().expect("Semicolon expected") {
-                        LexItem::Endl => (),
-                        s => return Err(format!("Expected semicolon, found {}", s.to_string())),
+            match t.checked_add(step) {
+                Some(next) if step > 0 => t = next,
+                _ => break,
+            }
+        }
+
+        series
+    }
+
+    fn summary(&self) -> LeasesSummary {
+        let mut summary = LeasesSummary {
+            total: self.0.len(),
+            unique_macs: self.macs().len(),
+            unique_ips: self.ips().len(),
+            ..Default::default()
+        };
+        let mut durations = Vec::new();
+
+        for l in &self.0 {
+            match &l.binding_state {
+                Some(state) => *summary.by_binding_state.entry(state.clone()).or_default() += 1,
+                None => summary.without_binding_state += 1,
+            }
+            if l.abandoned || l.binding_state == Some(BindingState::Abandoned) {
+                summary.abandoned += 1;
+            }
+
+            if let Some(starts) = l.dates.starts {
+                summary.earliest_start =
+                    Some(summary.earliest_start.map_or(starts, |d| d.min(starts)));
+            }
+            if let Some(ends) = l.dates.ends {
+                summary.latest_end = Some(summary.latest_end.map_or(ends, |d| d.max(ends)));
+            }
+            let starts = l.dates.starts.and_then(|d| d.to_unix_timestamp());
+            let ends = l.dates.ends.and_then(|d| d.to_unix_timestamp());
+            if let Some(secs) = starts.zip(ends).and_then(|(s, e)| e.checked_sub(s)) {
+                if secs >= 0 {
+                    durations.push(secs as u64);
+                }
+            }
+        }
+
+        if !durations.is_empty() {
+            let average = durations.iter().sum::<u64>() / durations.len() as u64;
+            summary.average_duration = Some(Duration::from_secs(average));
+        }
+
+        summary
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lease {
+    /// Leased address
+    pub ip: IpAddr,
+    pub dates: LeaseDates,
+    /// Time the failover peer was told this lease expires (`tstp`)
+    pub tstp: Option<Date>,
+    pub binding_state: Option<BindingState>,
+    /// Flags following the state in `binding state`, e.g. `dynamic-bootp`
+    /// in `binding state active dynamic-bootp;`
+    pub binding_flags: Vec<String>,
+    /// State the lease moves to when it expires (`next binding state`)
+    pub next_binding_state: Option<BindingState>,
+    /// State to restore when a failover peer rewinds (`rewind binding state`)
+    pub rewind_binding_state: Option<BindingState>,
+    /// Lease expiry time last received from the failover peer (`tsfp`)
+    pub tsfp: Option<Date>,
+    /// Lease expiry time actually acknowledged by the failover peer (`atsfp`)
+    pub atsfp: Option<Date>,
+    pub hardware: Option<Hardware>,
+    pub uid: Option<Uid>,
+    pub client_hostname: Option<String>,
+    pub hostname: Option<String>,
+    /// Relay agent circuit identifier (`option agent.circuit-id`), decoded
+    /// from its quoted or hexadecimal form
+    pub agent_circuit_id: Option<Vec<u8>>,
+    /// Relay agent remote identifier (`option agent.remote-id`), decoded
+    /// from its quoted or hexadecimal form
+    pub agent_remote_id: Option<Vec<u8>>,
+    /// Every `option name value;` statement, values kept as written
+    pub options: HashMap<String, String>,
+    /// Variables assigned with `set name = value;`, values kept as written
+    pub variables: HashMap<String, String>,
+    pub ddns: Option<DdnsInfo>,
+    pub billing: Option<Billing>,
+    pub on_events: Vec<LeaseEvent>,
+    /// Raw text of every occurrence of statements repeated within the lease
+    /// block, keyed by statement (`starts`, `option agent.circuit-id`, ...).
+    /// Only filled in with `DuplicatePolicy::CollectAll`.
+    pub duplicates: HashMap<String, Vec<String>>,
+    /// Comments found between the statements of the lease block, without
+    /// the leading `#`. Only filled in with `ParserOptions::preserve_comments`.
+    pub comments: Vec<String>,
+    /// Raw text of the statements the parser does not know about. Only filled
+    /// in with `ParserOptions::passthrough_unknown`, otherwise such statements
+    /// are an error, or skipped in `ParseMode::Lenient`.
+    pub unknown_statements: Vec<String>,
+    pub abandoned: bool,
+    pub deleted: bool,
+    pub bootp: bool,
+    pub reserved: bool,
+}
+
+/// Hashes the identifying fields of the lease (address, dates, binding state
+/// and client), so that exact duplicates collapse in a `HashSet`
+impl Hash for Lease {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.ip.hash(state);
+        self.dates.hash(state);
+        self.binding_state.hash(state);
+        self.hardware.hash(state);
+        self.uid.hash(state);
+    }
+}
+
+impl Lease {
+    /// Creates an empty lease of the given address
+    pub fn with_ip<I: Into<IpAddr>>(ip: I) -> Lease {
+        Lease {
+            ip: ip.into(),
+            dates: LeaseDates {
+                starts: None,
+                ends: None,
+                never_ends: false,
+            },
+            tstp: None,
+            binding_state: None,
+            binding_flags: Vec::new(),
+            next_binding_state: None,
+            rewind_binding_state: None,
+            tsfp: None,
+            atsfp: None,
+            hardware: None,
+            uid: None,
+            client_hostname: None,
+            hostname: None,
+            agent_circuit_id: None,
+            agent_remote_id: None,
+            options: HashMap::new(),
+            variables: HashMap::new(),
+            ddns: None,
+            billing: None,
+            on_events: Vec::new(),
+            duplicates: HashMap::new(),
+            comments: Vec::new(),
+            unknown_statements: Vec::new(),
+            abandoned: false,
+            deleted: false,
+            bootp: false,
+            reserved: false,
+        }
+    }
+
+    /// Starts building a lease, see `LeaseBuilder`
+    pub fn builder() -> LeaseBuilder {
+        LeaseBuilder::new()
+    }
+
+    /// Whether the lease is active at the given date. Leases ending `never`
+    /// are active at any date past their start. With the `chrono` or `time`
+    /// features enabled, their date times are accepted as well.
+    pub fn is_active_at<D: Into<Date>>(&self, when: D) -> bool {
+        self.dates.range().contains(&when.into())
+    }
+
+    /// Whether the lease has expired at the given date. The binding state is
+    /// authoritative: the lease is expired only when its state is `expired`,
+    /// whatever its end date. Leases without a binding state are expired
+    /// once their end date has passed.
+    pub fn is_expired_at<D: Into<Date>>(&self, when: D) -> bool {
+        match &self.binding_state {
+            Some(state) => *state == BindingState::Expired,
+            None => self.dates.ends.is_some_and(|ends| ends < when.into()),
+        }
+    }
+
+    /// Whether the address is free to be leased (`binding state free;`)
+    pub fn is_free(&self) -> bool {
+        self.binding_state == Some(BindingState::Free)
+    }
+
+    /// Whether the client released the lease (`binding state released;`)
+    pub fn is_released(&self) -> bool {
+        self.binding_state == Some(BindingState::Released)
+    }
+
+    /// Returns the client identifier as raw bytes, see `Uid::as_bytes`
+    pub fn uid_bytes(&self) -> Option<Vec<u8>> {
+        self.uid.as_ref().map(|uid| uid.as_bytes().to_vec())
+    }
+
+    /// Returns the client identifier in a canonical form (`01:0c:ce:ff`)
+    /// regardless of the format it was written in
+    pub fn normalized_uid(&self) -> Option<String> {
+        self.uid.as_ref().map(|uid| uid.to_string())
+    }
+
+    /// Returns the DUID of clients using RFC 4361 node-specific client
+    /// identifiers (type 255 followed by an IAID and a DUID)
+    pub fn client_duid(&self) -> Option<Duid> {
+        let uid = self.uid_bytes()?;
+        if uid.len() < 7 || uid[0] != 255 {
+            return None;
+        }
+
+        Duid::from_bytes(&uid[5..]).ok()
+    }
+
+    /// Returns the unquoted value of the `vendor-class-identifier` variable
+    pub fn vendor_class_identifier(&self) -> Option<String> {
+        self.variables
+            .get("vendor-class-identifier")
+            .map(|v| unquote(v.clone()))
+    }
+}
+
+type LeaseFilter<'a> = Box<dyn Fn(&Lease) -> bool + 'a>;
+
+/// Composable lease lookup. Every criterion narrows down the matching
+/// leases, which are borrowed from the collection:
+/// `leases.query().mac("aa:bb:cc:dd:ee:ff").active_at(now).latest()`
+pub struct LeaseQuery<'a> {
+    leases: &'a [Lease],
+    filters: Vec<LeaseFilter<'a>>,
+}
+
+impl<'a> LeaseQuery<'a> {
+    fn new(leases: &'a [Lease]) -> LeaseQuery<'a> {
+        LeaseQuery {
+            leases,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Leases matching an arbitrary predicate
+    pub fn filter<F: Fn(&Lease) -> bool + 'a>(mut self, f: F) -> LeaseQuery<'a> {
+        self.filters.push(Box::new(f));
+        self
+    }
+
+    pub fn ip<I: Into<IpAddr>>(self, ip: I) -> LeaseQuery<'a> {
+        let ip = ip.into();
+        self.filter(move |l| l.ip == ip)
+    }
+
+    /// Leases of a hardware address, compared in normalized form. An invalid
+    /// address matches no lease.
+    pub fn mac<S: AsRef<str>>(self, mac: S) -> LeaseQuery<'a> {
+        let mac = mac.as_ref().parse::<MacAddr>().ok();
+        self.filter(move |l| {
+            mac.is_some() && l.hardware.as_ref().and_then(|h| h.mac.as_ref()) == mac.as_ref()
+        })
+    }
+
+    pub fn hostname<S: Into<String>>(self, hostname: S) -> LeaseQuery<'a> {
+        let hostname = hostname.into();
+        self.filter(move |l| l.hostname.as_ref() == Some(&hostname))
+    }
+
+    pub fn client_hostname<S: Into<String>>(self, hostname: S) -> LeaseQuery<'a> {
+        let hostname = hostname.into();
+        self.filter(move |l| l.client_hostname.as_ref() == Some(&hostname))
+    }
+
+    pub fn binding(self, state: BindingState) -> LeaseQuery<'a> {
+        self.filter(move |l| l.binding_state.as_ref() == Some(&state))
+    }
+
+    /// Leases active at the given date and not abandoned
+    pub fn active_at<D: Into<Date>>(self, when: D) -> LeaseQuery<'a> {
+        let when = when.into();
+        self.filter(move |l| l.is_active_at(when) && !l.abandoned)
+    }
+
+    fn matches(&self, lease: &Lease) -> bool {
+        self.filters.iter().all(|f| f(lease))
+    }
+
+    /// Matching leases in file order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a Lease> + '_ {
+        self.leases.iter().filter(move |l| self.matches(l))
+    }
+
+    pub fn all(&self) -> Vec<&'a Lease> {
+        self.iter().collect()
+    }
+
+    pub fn first(&self) -> Option<&'a Lease> {
+        self.iter().next()
+    }
+
+    /// The last matching lease in the file, which is the current one
+    pub fn latest(&self) -> Option<&'a Lease> {
+        self.iter().next_back()
+    }
+
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+}
+
+/// Leases ordered by start date, see `LeasesMethods::sorted_by_start`.
+/// Leases starting at the same date keep their file order, leases without a
+/// start date are left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortedLeases<'a> {
+    leases: Vec<&'a Lease>,
+}
+
+impl<'a> SortedLeases<'a> {
+    fn new(leases: &'a [Lease]) -> SortedLeases<'a> {
+        let mut leases: Vec<&Lease> = leases.iter().filter(|l| l.dates.starts.is_some()).collect();
+        leases.sort_by_key(|l| l.dates.starts);
+        SortedLeases { leases }
+    }
+
+    /// Leases starting between `from` and `to`, both included, found by
+    /// binary search
+    pub fn leases_starting_between<D: Into<Date>>(&self, from: D, to: D) -> &[&'a Lease] {
+        let (from, to) = (Some(from.into()), Some(to.into()));
+        let lower = self.leases.partition_point(|l| l.dates.starts < from);
+        let upper = self.leases.partition_point(|l| l.dates.starts <= to);
+        if lower >= upper {
+            return &[];
+        }
+        &self.leases[lower..upper]
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a Lease> + '_ {
+        self.leases.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.leases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leases.is_empty()
+    }
+}
+
+/// Builds a `Lease` programmatically. The address is required, dates are
+/// checked for consistency when building.
+#[derive(Clone, Debug, Default)]
+pub struct LeaseBuilder {
+    ip: Option<IpAddr>,
+    starts: Option<Date>,
+    ends: Option<Date>,
+    never_ends: bool,
+    binding_state: Option<BindingState>,
+    next_binding_state: Option<BindingState>,
+    hardware: Option<Hardware>,
+    uid: Option<Uid>,
+    client_hostname: Option<String>,
+    hostname: Option<String>,
+    options: HashMap<String, String>,
+    variables: HashMap<String, String>,
+    abandoned: bool,
+}
+
+impl LeaseBuilder {
+    pub fn new() -> LeaseBuilder {
+        LeaseBuilder::default()
+    }
+
+    pub fn ip<I: Into<IpAddr>>(mut self, ip: I) -> LeaseBuilder {
+        self.ip = Some(ip.into());
+        self
+    }
+
+    pub fn starts(mut self, starts: Date) -> LeaseBuilder {
+        self.starts = Some(starts);
+        self
+    }
+
+    pub fn ends(mut self, ends: Date) -> LeaseBuilder {
+        self.ends = Some(ends);
+        self
+    }
+
+    /// Infinite lease (`ends never;`)
+    pub fn ends_never(mut self) -> LeaseBuilder {
+        self.never_ends = true;
+        self
+    }
+
+    pub fn binding_state(mut self, state: BindingState) -> LeaseBuilder {
+        self.binding_state = Some(state);
+        self
+    }
+
+    pub fn next_binding_state(mut self, state: BindingState) -> LeaseBuilder {
+        self.next_binding_state = Some(state);
+        self
+    }
+
+    pub fn hardware(mut self, hardware: Hardware) -> LeaseBuilder {
+        self.hardware = Some(hardware);
+        self
+    }
+
+    pub fn uid(mut self, uid: Uid) -> LeaseBuilder {
+        self.uid = Some(uid);
+        self
+    }
+
+    pub fn client_hostname<S: Into<String>>(mut self, hostname: S) -> LeaseBuilder {
+        self.client_hostname = Some(hostname.into());
+        self
+    }
+
+    pub fn hostname<S: Into<String>>(mut self, hostname: S) -> LeaseBuilder {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Adds an `option name value;` statement, the value is kept as written
+    pub fn option<S: Into<String>, V: Into<String>>(mut self, name: S, value: V) -> LeaseBuilder {
+        self.options.insert(name.into(), value.into());
+        self
+    }
+
+    /// Adds a `set name = value;` statement, the value is kept as written
+    pub fn variable<S: Into<String>, V: Into<String>>(mut self, name: S, value: V) -> LeaseBuilder {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn abandoned(mut self) -> LeaseBuilder {
+        self.abandoned = true;
+        self
+    }
+
+    /// Builds the lease. Fails when no address was given, when the lease
+    /// ends before it starts, or when it has both an end date and `ends never`.
+    pub fn build(self) -> Result<Lease, String> {
+        let ip = self
+            .ip
+            .ok_or_else(|| "Lease address is required".to_owned())?;
+
+        if let (Some(starts), Some(ends)) = (self.starts, self.ends) {
+            if ends < starts {
+                return Err(format!(
+                    "Lease {} ends ({}) before it starts ({})",
+                    ip, ends, starts
+                ));
+            }
+        }
+        if self.never_ends && self.ends.is_some() {
+            return Err(format!("Lease {} cannot both end and never end", ip));
+        }
+
+        let mut lease = Lease::with_ip(ip);
+        lease.dates = LeaseDates {
+            starts: self.starts,
+            ends: self.ends,
+            never_ends: self.never_ends,
+        };
+        lease.binding_state = self.binding_state;
+        lease.next_binding_state = self.next_binding_state;
+        lease.hardware = self.hardware;
+        lease.uid = self.uid;
+        lease.client_hostname = self.client_hostname;
+        lease.hostname = self.hostname;
+        lease.options = self.options;
+        lease.variables = self.variables;
+        lease.abandoned = self.abandoned;
+
+        Ok(lease)
+    }
+}
+
+/// Parses the statements of a lease block. The iterator is expected to point
+/// at the first token after the opening brace and is left pointing at the
+/// closing brace.
+pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    lease: &mut Lease,
+    iter: &mut Peekable<T>,
+    ctx: &mut ParseContext,
+) -> Result<(), ParseError> {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut ends_at = None;
+
+    while let Some(&nc) = iter.peek() {
+        if nc == &LexItem::Paren('}') {
+            if let (Some(starts), Some(ends)) = (&lease.dates.starts, &lease.dates.ends) {
+                if ends < starts {
+                    ctx.report(
+                        Severity::Warning,
+                        ends_at.unwrap_or(nc),
+                        format!("Lease {} ends before it starts", lease.ip),
+                    );
+                }
+            }
+            return Ok(());
+        }
+
+        if let LexItem::Opt(
+            kw @ (LeaseKeyword::Starts
+            | LeaseKeyword::Ends
+            | LeaseKeyword::Tstp
+            | LeaseKeyword::Tsfp
+            | LeaseKeyword::Atsfp),
+        ) = nc
+        {
+            if kw == &LeaseKeyword::Ends {
+                ends_at = Some(nc);
+            }
+            // checked as written, before any utc offset normalizes it
+            if let Ok(date) = parse_date(&mut iter.clone(), "date", 0) {
+                check_date(&date, nc, lease, ctx);
+            }
+        }
+
+        let single_valued = match nc {
+            LexItem::Opt(kw) => kw.is_single_valued(),
+            _ => false,
+        };
+        if single_valued {
+            let raw = parse_raw_statement(&mut iter.clone())?;
+            let key = statement_key(&raw);
+
+            if let Some(first) = seen.get(&key) {
+                ctx.report(
+                    Severity::Warning,
+                    nc,
+                    format!("'{}' repeated in lease {}", key, lease.ip),
+                );
+
+                match ctx.options.duplicates {
+                    DuplicatePolicy::LastWins => (),
+                    DuplicatePolicy::FirstWins => {
+                        // still parsed to validate it, the result is dropped
+                        parse_lease_statement(&mut lease.clone(), iter, &ctx.options)?;
+                        iter.next();
+                        continue;
+                    }
+                    DuplicatePolicy::CollectAll => {
+                        let first = first.clone();
+                        lease
+                            .duplicates
+                            .entry(key.clone())
+                            .or_insert_with(|| vec![first])
+                            .push(raw);
                     }
                 }
+            } else {
+                seen.insert(key, raw);
+            }
+        }
+
+        let unknown = matches!(nc, LexItem::Word(_) | LexItem::Decl(_));
+        if unknown && ctx.options.passthrough_unknown {
+            lease.unknown_statements.push(parse_raw_statement(iter)?);
+        } else if unknown && ctx.options.mode == ParseMode::Lenient {
+            let statement = parse_raw_statement(iter)?;
+            ctx.report(
+                Severity::Warning,
+                nc,
+                format!(
+                    "Skipped unknown statement '{}' in lease {}",
+                    statement, lease.ip
+                ),
+            );
+        } else {
+            parse_lease_statement(lease, iter, &ctx.options)?;
+        }
+        iter.next();
+    }
+
+    Ok(())
+}
+
+/// Reports dates that do not exist, such as February 30th, dates too far away
+/// to be checked, and weekdays that do not match the date
+fn check_date(date: &Date, statement: &LexItem, lease: &Lease, ctx: &mut ParseContext) {
+    let actual = match date.to_unix_timestamp() {
+        Some(timestamp) => Date::from_unix_timestamp(timestamp),
+        None => {
+            ctx.report(
+                Severity::Warning,
+                statement,
+                format!("{} in lease {} is out of range", date, lease.ip),
+            );
+            return;
+        }
+    };
+    if actual != *date {
+        ctx.report(
+            Severity::Warning,
+            statement,
+            format!(
+                "{} in lease {} does not exist, read as {}",
+                date, lease.ip, actual
+            ),
+        );
+    } else if actual.weekday != date.weekday {
+        ctx.report(
+            Severity::Info,
+            statement,
+            format!(
+                "Weekday {} of {} in lease {} should be {}",
+                date.weekday, date, lease.ip, actual.weekday
+            ),
+        );
+    }
+}
 
-                lease.dates.starts.replace(Date::from(weekday, date, time)?);
+fn parse_lease_statement<'l, T: Iterator<Item = &'l LexItem>>(
+    lease: &mut Lease,
+    iter: &mut Peekable<T>,
+    options: &ParserOptions,
+) -> Result<(), ParseError> {
+    if let Some(&nc) = iter.peek() {
+        match nc {
+            LexItem::Comment(c) => {
+                lease.comments.push(c.clone());
+            }
+            LexItem::Opt(LeaseKeyword::Starts) => {
+                lease.dates.starts = Some(parse_date(iter, "start date", options.utc_offset)?);
             }
             LexItem::Opt(LeaseKeyword::Ends) => {
+                lease.dates.ends = parse_end_date(iter, "end date", options.utc_offset)?;
+                lease.dates.never_ends = lease.dates.ends.is_none();
+            }
+            LexItem::Opt(LeaseKeyword::Tstp) => {
+                lease.tstp = Some(parse_date(iter, "tstp date", options.utc_offset)?);
+            }
+            LexItem::Opt(LeaseKeyword::Tsfp) => {
+                lease.tsfp = Some(parse_date(iter, "tsfp date", options.utc_offset)?);
+            }
+            LexItem::Opt(LeaseKeyword::Atsfp) => {
+                lease.atsfp = Some(parse_date(iter, "atsfp date", options.utc_offset)?);
+            }
+            LexItem::Opt(LeaseKeyword::Binding) => {
+                let (state, flags) = parse_binding_state(iter)?;
+                lease.binding_state.replace(state);
+                lease.binding_flags = flags;
+            }
+            LexItem::Opt(LeaseKeyword::Next) => {
                 iter.next();
-                let weekday = iter
-                    .peek()
-                    .expect("Weekday for end date expected")
-                    .to_string();
-                iter.next();
-                let date = iter.peek().expect("Date for end date expected").to_string();
+                let (state, _) = parse_binding_state(iter)?;
+                lease.next_binding_state.replace(state);
+            }
+            LexItem::Opt(LeaseKeyword::Rewind) => {
                 iter.next();
-                let time = iter.peek().expect("Time for end date expected").to_string();
+                let (state, _) = parse_binding_state(iter)?;
+                lease.rewind_binding_state.replace(state);
+            }
+            LexItem::Opt(LeaseKeyword::Hardware) => {
+                lease.hardware.replace(parse_hardware(iter)?);
+            }
+            LexItem::Opt(LeaseKeyword::Uid) => {
                 iter.next();
-                let tz = iter
+                let uid = iter
                     .peek()
-                    .expect("Timezone or semicolon expected")
+                    .ok_or_else(|| ParseError::eof("Client identifier expected"))?
                     .to_string();
+                lease.uid.replace(uid.parse()?);
 
-                if tz != LexItem::Endl.to_string() {
-                    iter.next();
-                    match iter.peek().expect("Semicolon expected") {
-                        LexItem::Endl => (),
-                        s => return Err(format!("Expected semicolon, found {}", s.to_string())),
-                    }
-                }
-
-                lease.dates.ends.replace(Date::from(weekday, date, time)?);
+                iter.next();
+                expect_semicolon(iter)?;
             }
-            LexItem::Opt(LeaseKeyword::Hardware) => {
+            LexItem::Opt(LeaseKeyword::ClientHostname) => {
                 iter.next();
-                let h_type = iter.peek().expect("Hardware type expected").to_string();
+                lease.client_hostname.replace(unquote(
+                    iter.peek()
+                        .ok_or_else(|| ParseError::eof("Client hostname expected"))?
+                        .to_string(),
+                ));
+
                 iter.next();
-                let mac = iter.peek().expect("MAC address expected").to_string();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Opt(LeaseKeyword::Hostname) => {
                 iter.next();
-                match iter.peek().expect("Semicolon expected") {
-                    LexItem::Endl => (),
-                    s => return Err(format!("Expected semicolon, found {}", s.to_string())),
-                }
+                lease.hostname.replace(unquote(
+                    iter.peek()
+                        .ok_or_else(|| ParseError::eof("Hostname expected"))?
+                        .to_string(),
+                ));
 
-                lease.hardware.replace(Hardware {
-                    h_type: h_type,
-                    mac: mac,
-                });
+                iter.next();
+                expect_semicolon(iter)?;
             }
-            LexItem::Opt(LeaseKeyword::Uid) => {
+            LexItem::Opt(LeaseKeyword::Option) => {
                 iter.next();
-                lease
-                    .uid
-                    .replace(iter.peek().expect("Client identifier expected").to_string());
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Option name expected"))?
+                    .to_string();
+                let value = parse_value(iter, &name)?;
 
-                iter.next();
-                match iter.peek().expect("Semicolon expected") {
-                    LexItem::Endl => (),
-                    s => return Err(format!("Expected semicolon, found {}", s.to_string())),
+                match name.as_str() {
+                    "agent.circuit-id" => {
+                        lease.agent_circuit_id.replace(decode_identifier(&value)?);
+                    }
+                    "agent.remote-id" => {
+                        lease.agent_remote_id.replace(decode_identifier(&value)?);
+                    }
+                    _ => (),
                 }
+                lease.options.insert(name, value);
             }
-            LexItem::Opt(LeaseKeyword::ClientHostname) => {
+            LexItem::Opt(LeaseKeyword::Set) => {
                 iter.next();
-                lease.client_hostname.replace(unquote_hostname(
-                    iter.peek().expect("Client hostname expected").to_string(),
-                ));
-
+                let name = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Variable name expected"))?
+                    .to_string();
                 iter.next();
-                match iter.peek().expect("Semicolon expected") {
-                    LexItem::Endl => (),
-                    s => return Err(format!("Expected semicolon, found {}", s.to_string())),
+                match iter.peek().ok_or_else(|| ParseError::eof("'=' expected"))? {
+                    LexItem::Word(w) if w == "=" => (),
+                    s => return Err(ParseError::unexpected(format!("Expected '=', found {}", s))),
+                }
+                let value = parse_value(iter, &name)?;
+
+                let mut ddns = lease.ddns.clone().unwrap_or_default();
+                if let Some(field) = ddns.field_mut(&name) {
+                    field.replace(unquote(value.clone()));
+                    lease.ddns.replace(ddns);
                 }
+
+                lease.variables.insert(name, value);
             }
-            LexItem::Opt(LeaseKeyword::Hostname) => {
+            LexItem::Opt(LeaseKeyword::Billing) => {
                 iter.next();
-                lease.hostname.replace(unquote_hostname(
-                    iter.peek().expect("Hostname expected").to_string(),
-                ));
+                let kind = iter
+                    .peek()
+                    .ok_or_else(|| ParseError::eof("Billing class expected"))?
+                    .to_string();
+                iter.next();
+                let class = unquote(
+                    iter.peek()
+                        .ok_or_else(|| ParseError::eof("Class name expected"))?
+                        .to_string(),
+                );
+                let subclass = match kind.as_str() {
+                    "class" => None,
+                    "subclass" => {
+                        iter.next();
+                        Some(unquote(
+                            iter.peek()
+                                .ok_or_else(|| ParseError::eof("Subclass data expected"))?
+                                .to_string(),
+                        ))
+                    }
+                    _ => {
+                        return Err(ParseError::unexpected(format!(
+                            "Expected class or subclass, found {}",
+                            kind
+                        )))
+                    }
+                };
+                iter.next();
+                expect_semicolon(iter)?;
 
+                lease.billing.replace(Billing { class, subclass });
+            }
+            LexItem::Opt(LeaseKeyword::On) => {
+                let mut events = Vec::new();
                 iter.next();
-                match iter.peek().expect("Semicolon expected") {
-                    LexItem::Endl => (),
-                    s => return Err(format!("Expected semicolon, found {}", s.to_string())),
+                loop {
+                    match iter
+                        .peek()
+                        .ok_or_else(|| ParseError::eof("Event name expected"))?
+                    {
+                        LexItem::Paren('{') => break,
+                        LexItem::Word(w) if w == "or" => (),
+                        e => events.push(e.to_string()),
+                    }
+                    iter.next();
                 }
+                if events.is_empty() {
+                    return Err(ParseError::unexpected("Event name expected"));
+                }
+                let statements = parse_block_statements(iter)?;
+
+                lease.on_events.push(LeaseEvent { events, statements });
             }
             LexItem::Opt(LeaseKeyword::Abandoned) => {
                 lease.abandoned = true;
                 iter.next();
-                match iter.peek().expect("Semicolon expected") {
-                    LexItem::Endl => (),
-                    s => return Err(format!("Expected semicolon, found {}", s.to_string())),
-                }
+                expect_semicolon(iter)?;
             }
-            LexItem::Paren('}') => {
-                return Ok(());
+            LexItem::Opt(LeaseKeyword::Reserved) => {
+                lease.reserved = true;
+                iter.next();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Opt(LeaseKeyword::Bootp) => {
+                lease.bootp = true;
+                iter.next();
+                expect_semicolon(iter)?;
+            }
+            LexItem::Opt(LeaseKeyword::Deleted) => {
+                lease.deleted = true;
+                iter.next();
+                expect_semicolon(iter)?;
             }
             _ => {
-                return Err(format!(
+                return Err(ParseError::unexpected(format!(
                     "Unexpected option '{}'",
-                    iter.peek().unwrap().to_string()
-                ));
+                    nc
+                )));
             }
         }
-        iter.next();
     }
 
     Ok(())
 }
 
-fn unquote_hostname(hn: String) -> String {
-    hn.replace("\"", "")
+/// Returns the name identifying a statement for duplicate detection, e.g.
+/// `starts` or `option agent.circuit-id`
+fn statement_key(raw: &str) -> String {
+    let words: Vec<&str> = raw.split(' ').collect();
+    match words[0] {
+        "option" | "set" | "next" | "rewind" if words.len() > 1 => words[..2].join(" "),
+        w => w.to_owned(),
+    }
+}
+
+/// Parses the value of an `ends` statement, which is either a date or
+/// `never`, see `parse_date`. Returns `None` for `never`.
+pub(crate) fn parse_end_date<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    name: &str,
+    utc_offset: i64,
+) -> Result<Option<Date>, ParseError> {
+    iter.next();
+    if iter.peek().map(|t| t.to_string()).as_deref() != Some("never") {
+        return parse_date_value(iter, name, utc_offset).map(Some);
+    }
+
+    iter.next();
+    match iter.peek() {
+        Some(LexItem::Endl) => Ok(None),
+        _ => Err(ParseError::unexpected(format!(
+            "Expected semicolon after never {}",
+            name
+        ))),
+    }
+}
+
+/// Parses the `weekday year/month/day hour:minute:second [timezone];` or
+/// `epoch seconds;` value of a date statement. The iterator is expected to
+/// point at the statement keyword and is left pointing at the terminating
+/// semicolon. Dates are converted to UTC: the time zone is `UTC`, `GMT` or
+/// an offset such as `+0200`, and dates written without one are
+/// `utc_offset` seconds ahead of UTC.
+pub(crate) fn parse_date<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    name: &str,
+    utc_offset: i64,
+) -> Result<Date, ParseError> {
+    iter.next();
+    parse_date_value(iter, name, utc_offset)
+}
+
+/// Same as `parse_date`, with the iterator pointing at the first token of
+/// the value
+fn parse_date_value<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    name: &str,
+    utc_offset: i64,
+) -> Result<Date, ParseError> {
+    let weekday = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof(format!("Weekday for {} expected", name)))?
+        .to_string();
+    if weekday == "never" {
+        // dhcpd only writes never for end dates
+        return Err(ParseError::invalid_date(format!(
+            "{} cannot be never",
+            name
+        )));
+    }
+    if weekday == "epoch" {
+        iter.next();
+        let timestamp = iter
+            .peek()
+            .ok_or_else(|| ParseError::eof(format!("Timestamp for {} expected", name)))?
+            .to_string()
+            .parse::<i64>()
+            .map_err(|e| {
+                ParseError::invalid_date(format!("Invalid timestamp for {}: {}", name, e))
+            })?;
+        iter.next();
+        return match iter.peek() {
+            Some(LexItem::Endl) => Ok(Date::from_unix_timestamp(timestamp)),
+            _ => Err(ParseError::unexpected(format!(
+                "Expected semicolon after {} timestamp",
+                name
+            ))),
+        };
+    }
+
+    iter.next();
+    let date = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof(format!("Date for {} expected", name)))?
+        .to_string();
+    iter.next();
+    let time = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof(format!("Time for {} expected", name)))?
+        .to_string();
+    iter.next();
+
+    let tz = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof("Timezone or semicolon expected"))?
+        .to_string();
+    let utc_offset = if tz != LexItem::Endl.to_string() {
+        let offset = match tz.as_str() {
+            "UTC" | "GMT" => Some(0),
+            tz => parse_utc_offset(tz),
+        }
+        .ok_or_else(|| {
+            ParseError::invalid_date(format!("Invalid time zone {} for {}", tz, name))
+        })?;
+        iter.next();
+        expect_semicolon(iter)?;
+        offset
+    } else {
+        utc_offset
+    };
+
+    let date = Date::from(weekday, date, time)?;
+    if utc_offset == 0 {
+        return Ok(date);
+    }
+    let timestamp = date
+        .to_unix_timestamp()
+        .and_then(|ts| ts.checked_sub(utc_offset))
+        .ok_or_else(|| ParseError::invalid_date(format!("{} {} is out of range", name, date)))?;
+    Ok(Date::from_unix_timestamp(timestamp))
+}
+
+/// Reads the value of a statement up to the terminating semicolon. Values
+/// spanning several tokens are joined with a single space. The iterator is
+/// expected to point at the token preceding the value and is left pointing at
+/// the semicolon.
+pub(crate) fn parse_value<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+    name: &str,
+) -> Result<String, ParseError> {
+    let mut words = Vec::new();
+
+    iter.next();
+    loop {
+        match iter.peek() {
+            Some(LexItem::Endl) => break,
+            Some(LexItem::Paren(p)) => {
+                return Err(ParseError::unexpected(format!(
+                    "Expected semicolon, found {}",
+                    p
+                )));
+            }
+            Some(t) => words.push(t.to_string()),
+            None => {
+                return Err(ParseError::eof(format!(
+                    "Semicolon expected after {}",
+                    name
+                )))
+            }
+        }
+        iter.next();
+    }
+
+    if words.is_empty() {
+        return Err(ParseError::unexpected(format!(
+            "Value for {} expected",
+            name
+        )));
+    }
+
+    Ok(words.join(" "))
+}
+
+/// Checks that the iterator points at the semicolon ending a statement
+pub(crate) fn expect_semicolon<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    match iter.peek() {
+        Some(LexItem::Endl) => Ok(()),
+        Some(s) => Err(ParseError::unexpected(format!(
+            "Expected semicolon, found {}",
+            s
+        ))),
+        None => Err(ParseError::eof("Semicolon expected")),
+    }
+}
+
+/// Reads a single statement as raw text without interpreting it. The
+/// iterator is expected to point at the first token of the statement and is
+/// left pointing at the terminating semicolon or, for statements ending with a
+/// block, at the closing brace of that block.
+pub(crate) fn parse_raw_statement<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<String, ParseError> {
+    let mut words: Vec<String> = Vec::new();
+    let mut depth = 0;
+
+    loop {
+        let token = match iter.peek() {
+            Some(t) => t,
+            None => return Err(ParseError::eof("Semicolon expected")),
+        };
+
+        match token {
+            LexItem::Endl if depth == 0 => break,
+            LexItem::Endl => {
+                let last = words.pop().unwrap_or_default();
+                words.push(last + ";");
+            }
+            LexItem::Paren('{') => {
+                depth += 1;
+                words.push(token.to_string());
+            }
+            LexItem::Paren('}') if depth == 0 => {
+                return Err(ParseError::unexpected("Expected semicolon, found }"));
+            }
+            LexItem::Paren('}') => {
+                depth -= 1;
+                words.push(token.to_string());
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => words.push(token.to_string()),
+        }
+        iter.next();
+    }
+
+    Ok(words.join(" "))
+}
+
+/// Reads a `{ ... }` block as a list of raw statements without interpreting
+/// them. Nested blocks are kept as part of the statement they belong to. The
+/// iterator is expected to point at the opening brace and is left pointing at
+/// the matching closing brace.
+pub(crate) fn parse_block_statements<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<Vec<String>, ParseError> {
+    let mut statements = Vec::new();
+
+    iter.next();
+    loop {
+        match iter.peek() {
+            Some(LexItem::Paren('}')) => break,
+            Some(LexItem::Endl) => (),
+            Some(_) => statements.push(parse_raw_statement(iter)?),
+            None => {
+                return Err(ParseError::unterminated(
+                    "Expected end of block with '}', got end of input",
+                ))
+            }
+        }
+        iter.next();
+    }
+
+    Ok(statements)
+}
+
+/// Parses the `binding state <state> [flags...];` statement into the state
+/// and the flags qualifying it. The iterator is expected to point at the
+/// `binding` keyword and is left pointing at the semicolon.
+pub(crate) fn parse_binding_state<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<(BindingState, Vec<String>), ParseError> {
+    match iter
+        .peek()
+        .ok_or_else(|| ParseError::eof("'binding' expected"))?
+    {
+        LexItem::Opt(LeaseKeyword::Binding) => (),
+        s => {
+            return Err(ParseError::unexpected(format!(
+                "Expected 'binding', found {}",
+                s
+            )))
+        }
+    }
+    iter.next();
+    match iter
+        .peek()
+        .ok_or_else(|| ParseError::eof("'state' expected"))?
+    {
+        LexItem::Word(w) if w == "state" => (),
+        s => {
+            return Err(ParseError::unexpected(format!(
+                "Expected 'state', found {}",
+                s
+            )))
+        }
+    }
+    let value = parse_value(iter, "binding state")?;
+
+    let mut words = value.split(' ');
+    let state = BindingState::try_from(words.next().unwrap_or_default())?;
+    let flags = words.map(|w| w.to_owned()).collect();
+
+    Ok((state, flags))
+}
+
+/// Parses the `hardware <type> <address>;` statement. The iterator is expected
+/// to point at the `hardware` keyword and is left pointing at the semicolon.
+pub(crate) fn parse_hardware<'l, T: Iterator<Item = &'l LexItem>>(
+    iter: &mut Peekable<T>,
+) -> Result<Hardware, ParseError> {
+    iter.next();
+    let h_type = iter
+        .peek()
+        .ok_or_else(|| ParseError::eof("Hardware type expected"))?
+        .to_string()
+        .parse::<HardwareType>()?;
+    iter.next();
+    let mac = match iter
+        .peek()
+        .ok_or_else(|| ParseError::eof("MAC address or semicolon expected"))?
+    {
+        LexItem::Endl => return Ok(Hardware { h_type, mac: None }),
+        m => m
+            .to_string()
+            .parse::<MacAddr>()
+            .map_err(|e| format!("Invalid hardware address: {}", e))?,
+    };
+    iter.next();
+    expect_semicolon(iter)?;
+
+    Ok(Hardware {
+        h_type,
+        mac: Some(mac),
+    })
+}
+
+/// Removes the quotes around a string and resolves its escape sequences.
+/// Values that are not quoted are returned as is.
+pub(crate) fn unquote(s: String) -> String {
+    let inner = match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return s,
+    };
+
+    match unescape_bytes(inner) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => inner.to_owned(),
+    }
 }
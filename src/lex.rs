@@ -1,8 +1,11 @@
 use std::fmt;
 use std::iter::Peekable;
 
+use crate::error::ParseError;
 use crate::leases::LeaseKeyword;
 use crate::parser::ConfigKeyword;
+use crate::parser::Limits;
+use crate::parser::Span;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LexItem {
@@ -11,6 +14,7 @@ pub enum LexItem {
     Word(String),
     Opt(LeaseKeyword),
     Decl(ConfigKeyword),
+    Comment(String),
 }
 
 impl fmt::Display for LexItem {
@@ -18,64 +22,174 @@ impl fmt::Display for LexItem {
         match self {
             LexItem::Paren(v) => v.fmt(f),
             LexItem::Word(v) => v.fmt(f),
-            LexItem::Opt(v) => write!(f, "{}", v.to_string()),
-            LexItem::Decl(v) => write!(f, "{}", v.to_string()),
+            LexItem::Opt(v) => v.fmt(f),
+            LexItem::Decl(v) => v.fmt(f),
             LexItem::Endl => write!(f, ";"),
+            LexItem::Comment(v) => write!(f, "# {}", v),
         }
     }
 }
 
-pub fn lex<S>(input: S) -> Result<Vec<LexItem>, String>
+pub fn lex<S>(input: S) -> Result<Vec<LexItem>, ParseError>
 where
     S: Into<String>,
 {
-    let mut result = Vec::new();
-
     let input_str = input.into();
+    let tokens = lex_spanned(&input_str)?;
+
+    Ok(tokens.into_iter().map(|(token, _)| token).collect())
+}
+
+/// Splits the input into tokens, along with the position of each token in
+/// the input
+pub fn lex_spanned(input: &str) -> Result<Vec<(LexItem, Span)>, ParseError> {
+    lex_spanned_with_limits(input, &Limits::default())
+}
+
+/// Same as `lex_spanned`, failing as soon as the input goes over the size,
+/// token count or nesting depth of `limits`
+pub fn lex_spanned_with_limits(
+    input: &str,
+    limits: &Limits,
+) -> Result<Vec<(LexItem, Span)>, ParseError> {
+    if let Some(max) = limits.max_input_size {
+        if input.len() > max {
+            return Err(ParseError::limit(format!(
+                "Input of {} bytes is over the limit of {} bytes",
+                input.len(),
+                max
+            )));
+        }
+    }
 
-    let mut it = input_str.chars().peekable();
-    while let Some(&c) = it.peek() {
-        match c {
+    let mut result = Vec::new();
+    let lines = LineIndex::new(input);
+    let mut depth: usize = 0;
+
+    let mut it = input.char_indices().peekable();
+    while let Some(&(start, c)) = it.peek() {
+        let token = match c {
             '(' | ')' | '[' | ']' | '{' | '}' => {
-                result.push(LexItem::Paren(c));
                 it.next();
+                LexItem::Paren(c)
+            }
+            c if c.is_whitespace() => {
+                it.next();
+                continue;
             }
-            ' ' | '\n' | '\t' => {
+            '#' => {
+                // comments run until the end of the line
                 it.next();
+                let mut comment = String::new();
+                while let Some(&(_, c)) = it.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                    it.next();
+                }
+                LexItem::Comment(comment.trim().to_owned())
             }
             ';' => {
-                result.push(LexItem::Endl);
                 it.next();
+                LexItem::Endl
             }
             _ => {
-                let w = get_word(&mut it);
-                let kw = ConfigKeyword::from(&w);
-                if kw.is_ok() {
-                    result.push(LexItem::Decl(kw.unwrap()));
+                let w = get_word(&mut it).map_err(|e| e.at(lines.span(input, start, start)))?;
+                if let Ok(kw) = ConfigKeyword::from(&w) {
+                    LexItem::Decl(kw)
+                } else if let Ok(kw) = LeaseKeyword::from(&w) {
+                    LexItem::Opt(kw)
                 } else {
-                    let kw = LeaseKeyword::from(&w);
-                    if kw.is_ok() {
-                        result.push(LexItem::Opt(kw.unwrap()));
-                    } else {
-                        result.push(LexItem::Word(w));
-                    }
+                    LexItem::Word(w)
                 }
             }
+        };
+
+        let end = it.peek().map_or(input.len(), |&(i, _)| i);
+        let span = lines.span(input, start, end);
+        match token {
+            LexItem::Paren('{') => depth += 1,
+            LexItem::Paren('}') => depth = depth.saturating_sub(1),
+            _ => (),
         }
+        if limits.max_depth.is_some_and(|max| depth > max) {
+            return Err(ParseError::limit("Blocks nested too deeply").at(span));
+        }
+        if limits.max_tokens.is_some_and(|max| result.len() >= max) {
+            return Err(ParseError::limit("Too many tokens in the input").at(span));
+        }
+        result.push((token, span));
     }
     Ok(result)
 }
 
-fn get_word<T: Iterator<Item = char>>(iter: &mut Peekable<T>) -> String {
+/// Position of the end of the input, where errors about missing tokens are
+/// reported
+pub fn end_span(input: &str) -> Span {
+    LineIndex::new(input).span(input, input.len(), input.len())
+}
+
+/// Offsets of the beginning of each line, to turn byte offsets into lines
+/// and columns
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(input: &str) -> LineIndex {
+        let mut starts = vec![0];
+        starts.extend(input.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { starts }
+    }
+
+    fn span(&self, input: &str, start: usize, end: usize) -> Span {
+        let line = self.starts.partition_point(|&s| s <= start);
+        let column = input[self.starts[line - 1]..start].chars().count() + 1;
+
+        Span {
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+}
+
+/// Reads a word up to the next whitespace or semicolon. Quoted strings are
+/// read as a single word, quotes and escapes included, so that they may
+/// contain spaces, semicolons or escaped quotes.
+fn get_word<T: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<T>,
+) -> Result<String, ParseError> {
     let mut word = String::new();
+    let mut quoted = false;
 
-    while let Some(&nc) = iter.peek() {
-        if nc.is_whitespace() || nc == ';' {
+    while let Some(&(_, nc)) = iter.peek() {
+        // a comment may directly follow a value, as in `00:00:00#UTC`, and
+        // parentheses are tokens of their own, as in `substring(option`
+        if !quoted && (nc.is_whitespace() || matches!(nc, ';' | '#' | '(' | ')')) {
             break;
         }
 
         word.push(nc);
         iter.next();
+
+        match nc {
+            '"' => quoted = !quoted,
+            '\\' if quoted => match iter.next() {
+                Some((_, escaped)) => word.push(escaped),
+                None => break,
+            },
+            _ => (),
+        }
+    }
+
+    if quoted {
+        return Err(ParseError::unterminated(format!(
+            "Unterminated string {}",
+            word
+        )));
     }
-    word
+    Ok(word)
 }
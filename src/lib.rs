@@ -1,5 +1,19 @@
+pub mod classes;
 pub mod common;
+pub mod conf;
+pub mod duid;
+pub mod error;
+pub mod groups;
+pub mod hosts;
+pub mod ia;
 pub mod leases;
+#[cfg(feature = "oui")]
+pub mod oui;
 pub mod parser;
 
 mod lex;
+
+// compiles and runs the examples of the README as doctests
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+mod readme {}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::common::MacAddr;
+use crate::error::ParseError;
+
+/// Vendors of hardware addresses, keyed by their Organizationally Unique
+/// Identifier (the first three octets). The database is not bundled, it is
+/// loaded from the registry published by the IEEE (`oui.txt`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OuiDatabase {
+    vendors: HashMap<[u8; 3], String>,
+}
+
+impl OuiDatabase {
+    pub fn new() -> OuiDatabase {
+        OuiDatabase::default()
+    }
+
+    /// Reads a registry file, see `FromStr`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<OuiDatabase, ParseError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|source| ParseError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        Ok(content.parse()?)
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, oui: [u8; 3], vendor: S) {
+        self.vendors.insert(oui, vendor.into());
+    }
+
+    /// Vendor of a hardware address, `None` when its OUI is unknown
+    pub fn lookup(&self, mac: &MacAddr) -> Option<&str> {
+        match mac.as_bytes() {
+            [a, b, c, ..] => self.vendors.get(&[*a, *b, *c]).map(|v| v.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.vendors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vendors.is_empty()
+    }
+}
+
+/// Parses the first three octets of a line (`00-00-0C`, `00:00:0C` or
+/// `00000C`)
+fn parse_oui(s: &str) -> Option<[u8; 3]> {
+    let hex: String = s.chars().filter(|c| *c != '-' && *c != ':').collect();
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([byte(0)?, byte(2)?, byte(4)?])
+}
+
+impl FromStr for OuiDatabase {
+    type Err = String;
+
+    /// Parses an IEEE `oui.txt` registry, made of lines like
+    /// `00-00-0C   (hex)   Cisco Systems, Inc`. Lines that do not start
+    /// with an OUI followed by a vendor name are skipped.
+    fn from_str(s: &str) -> Result<OuiDatabase, String> {
+        let mut db = OuiDatabase::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+            let (oui, rest) = match line.split_once(char::is_whitespace) {
+                Some(split) => split,
+                None => continue,
+            };
+            let oui = match parse_oui(oui) {
+                Some(oui) => oui,
+                None => continue,
+            };
+            let rest = rest.trim_start();
+            let vendor = rest
+                .strip_prefix("(hex)")
+                .or_else(|| rest.strip_prefix("(base 16)"))
+                .unwrap_or(rest)
+                .trim();
+
+            if !vendor.is_empty() {
+                db.vendors.entry(oui).or_insert_with(|| vendor.to_owned());
+            }
+        }
+
+        Ok(db)
+    }
+}
@@ -1,85 +1,667 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::iter::Peekable;
+use std::net::IpAddr;
+use std::path::Path;
+
+use crate::classes::parse_class;
+use crate::classes::Class;
+use crate::common::Date;
+pub use crate::error::ParseError;
+use crate::groups::parse_group;
+use crate::groups::Group;
+use crate::hosts::parse_host;
+use crate::hosts::Host;
+use crate::ia::parse_ia;
+use crate::ia::Ia;
+use crate::ia::IaKind;
 use crate::leases::parse_lease;
+use crate::leases::parse_raw_statement;
+use crate::leases::parse_value;
+use crate::leases::unquote;
 use crate::leases::Lease;
 use crate::leases::Leases;
 pub use crate::leases::LeasesMethods;
-use crate::lex::lex;
+use crate::lex::end_span;
+use crate::lex::lex_spanned_with_limits;
 use crate::lex::LexItem;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParserResult {
     pub leases: Leases,
+    /// DUID of the server that wrote the file (`server-duid`), as written
+    pub server_duid: Option<String>,
+    /// Byte order of the server that wrote the file (`authoring-byte-order`)
+    pub authoring_byte_order: Option<String>,
+    pub hosts: Vec<Host>,
+    pub groups: Vec<Group>,
+    pub classes: Vec<Class>,
+    /// DHCPv6 identity associations
+    pub ias: Vec<Ia>,
+    /// Information from the comments heading the file
+    pub file_info: FileInfo,
+    /// Non-fatal issues found while parsing
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Metadata found in the comments at the top of a leases file, such as
+/// `# This lease file was written by isc-dhcp-4.4.1`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileInfo {
+    /// Version of the server that wrote the file, e.g. `isc-dhcp-4.4.1`
+    pub server_version: Option<String>,
+    /// Time the file was written, when the header carries one
+    pub written: Option<Date>,
+    /// Header comments, without the leading `#`
+    pub comments: Vec<String>,
+}
+
+impl FileInfo {
+    fn from_comments(comments: Vec<String>) -> FileInfo {
+        let mut info = FileInfo::default();
+
+        for comment in comments.iter() {
+            if let Some(pos) = comment.find("written by ") {
+                let version = comment[pos + "written by ".len()..].trim();
+                if !version.is_empty() {
+                    info.server_version = Some(version.to_owned());
+                }
+            }
+
+            let words: Vec<&str> = comment.split_whitespace().collect();
+            for w in words.windows(3) {
+                if let Some(date) = header_date(w[0], w[1], w[2]) {
+                    info.written = Some(date);
+                }
+            }
+        }
+
+        info.comments = comments;
+        info
+    }
+}
+
+/// Reads a `weekday year/month/day hour:minute:second` date out of the words
+/// of a comment
+fn header_date(weekday: &str, date: &str, time: &str) -> Option<Date> {
+    let numbers = |s: &str, sep: char| {
+        s.split(sep).count() == 3 && s.split(sep).all(|p| p.parse::<i64>().is_ok())
+    };
+
+    if weekday.parse::<i64>().is_err() || !numbers(date, '/') || !numbers(time, ':') {
+        return None;
+    }
+
+    Date::from(weekday, date, time).ok()
+}
+
+/// How statements repeated within a single lease block are handled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// The last occurrence is kept, as dhcpd itself does
+    #[default]
+    LastWins,
+    /// The first occurrence is kept
+    FirstWins,
+    /// The last occurrence is kept and the raw text of every occurrence is
+    /// collected into `Lease::duplicates`
+    CollectAll,
+}
+
+/// How statements the parser does not know about are handled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Unknown statements are an error
+    #[default]
+    Strict,
+    /// Unknown statements of a lease block and unknown top level
+    /// declarations are skipped and reported in `ParserResult::diagnostics`,
+    /// so that files written by newer dhcpd versions can still be read
+    Lenient,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub mode: ParseMode,
+    pub duplicates: DuplicatePolicy,
+    /// Keep the comments found between the statements of a lease into
+    /// `Lease::comments` instead of discarding them
+    pub preserve_comments: bool,
+    /// Keep the statements of a lease block the parser does not understand
+    /// into `Lease::unknown_statements` instead of failing
+    pub passthrough_unknown: bool,
+    /// Offset from UTC, in seconds, of the dates written without a time
+    /// zone. dhcpd writes dates in UTC, but files edited or generated by
+    /// other tools may use local time. Such dates are converted to UTC,
+    /// `epoch` dates and dates with a time zone (`UTC`, `GMT` or an offset
+    /// such as `+0200`) are not affected.
+    pub utc_offset: i64,
+    /// Instead of failing on a malformed declaration, drop it, skip to the
+    /// next `lease` declaration and report the error in
+    /// `ParserResult::diagnostics`. Errors of the lexer, such as an
+    /// unterminated string, still fail the parsing.
+    pub recover: bool,
+    /// Bounds on the input, for files coming from untrusted sources
+    pub limits: Limits,
+}
+
+/// Bounds enforced while lexing and parsing, so that a hostile input cannot
+/// exhaust the memory. Going over a limit fails the parsing with
+/// `ParseError::LimitExceeded`, even in recover mode. Limits left to `None`
+/// are not enforced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Size of the input, in bytes
+    pub max_input_size: Option<usize>,
+    /// Number of tokens, comments included
+    pub max_tokens: Option<usize>,
+    /// Number of lease declarations
+    pub max_leases: Option<usize>,
+    /// Number of nested blocks
+    pub max_depth: Option<usize>,
+}
+
+/// Position of a token in the parsed input. Lines and columns start at 1,
+/// columns are counted in characters while `start` and `end` are byte
+/// offsets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// How serious a `Diagnostic` is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something worth knowing that does not change the parsed data
+    Info,
+    /// Input that was skipped, fixed up or is likely wrong
+    Warning,
+    /// A declaration that could not be parsed and was dropped, see
+    /// `ParserOptions::recover`
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Non-fatal issue found while parsing, such as a repeated statement or a
+/// date that does not exist
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Position of the statement the issue was found on
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{}: {} at {}", self.severity, self.message, span),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// State shared by the parsing functions
+pub struct ParseContext<'a> {
+    pub(crate) options: ParserOptions,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    tokens: &'a [LexItem],
+    spans: &'a [Span],
+}
+
+impl ParseContext<'_> {
+    /// Index of a token borrowed from the parsed token list
+    fn index_of(&self, token: &LexItem) -> Option<usize> {
+        let range = self.tokens.as_ptr_range();
+        let ptr = token as *const LexItem;
+        if !range.contains(&ptr) {
+            return None;
+        }
+        Some((ptr as usize - range.start as usize) / std::mem::size_of::<LexItem>())
+    }
+
+    /// Position of a token borrowed from the parsed token list
+    pub(crate) fn span_of(&self, token: &LexItem) -> Option<Span> {
+        self.index_of(token)
+            .and_then(|i| self.spans.get(i).copied())
+    }
+
+    /// Records a diagnostic about the statement starting at `token`
+    pub(crate) fn report<S: Into<String>>(
+        &mut self,
+        severity: Severity,
+        token: &LexItem,
+        message: S,
+    ) {
+        let span = self.span_of(token);
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message: message.into(),
+            span,
+        });
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConfigKeyword {
+    Class,
+    Group,
+    Host,
+    IaNa,
+    IaPd,
+    IaTa,
     Lease,
+    ServerDuid,
+    Subclass,
+    Subgroup,
+    AuthoringByteOrder,
 }
 
-impl ConfigKeyword {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for ConfigKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &ConfigKeyword::Lease => "lease".to_owned(),
+            ConfigKeyword::Class => write!(f, "class"),
+            ConfigKeyword::Group => write!(f, "group"),
+            ConfigKeyword::Host => write!(f, "host"),
+            ConfigKeyword::IaNa => write!(f, "ia-na"),
+            ConfigKeyword::IaPd => write!(f, "ia-pd"),
+            ConfigKeyword::IaTa => write!(f, "ia-ta"),
+            ConfigKeyword::Lease => write!(f, "lease"),
+            ConfigKeyword::ServerDuid => write!(f, "server-duid"),
+            ConfigKeyword::Subclass => write!(f, "subclass"),
+            ConfigKeyword::Subgroup => write!(f, "subgroup"),
+            ConfigKeyword::AuthoringByteOrder => write!(f, "authoring-byte-order"),
         }
     }
+}
 
+impl ConfigKeyword {
     pub fn from(s: &str) -> Result<ConfigKeyword, String> {
         match s {
+            "class" => Ok(ConfigKeyword::Class),
+            "group" => Ok(ConfigKeyword::Group),
+            "host" => Ok(ConfigKeyword::Host),
+            "ia-na" => Ok(ConfigKeyword::IaNa),
+            "ia-pd" => Ok(ConfigKeyword::IaPd),
+            "ia-ta" => Ok(ConfigKeyword::IaTa),
             "lease" => Ok(ConfigKeyword::Lease),
+            "server-duid" => Ok(ConfigKeyword::ServerDuid),
+            "subclass" => Ok(ConfigKeyword::Subclass),
+            "subgroup" => Ok(ConfigKeyword::Subgroup),
+            "authoring-byte-order" => Ok(ConfigKeyword::AuthoringByteOrder),
             _ => Err(format!("'{}' declaration is not supported", s)),
         }
     }
 }
 
-fn parse_config(tokens: Vec<LexItem>) -> Result<ParserResult, String> {
-    let mut leases = Leases::new();
-    let lease = Lease::new();
+/// Drops comment tokens, except those placed between the statements of a
+/// lease block when comments are preserved
+fn filter_comments(tokens: Vec<(LexItem, Span)>, preserve: bool) -> Vec<(LexItem, Span)> {
+    let mut result: Vec<(LexItem, Span)> = Vec::with_capacity(tokens.len());
+    let mut in_lease = false;
+    let mut depth = 0;
+
+    for token in tokens {
+        match &token.0 {
+            LexItem::Comment(_) => {
+                let boundary = matches!(
+                    result.last(),
+                    Some((LexItem::Paren('{'), _))
+                        | Some((LexItem::Paren('}'), _))
+                        | Some((LexItem::Endl, _))
+                );
+                if preserve && in_lease && depth == 1 && boundary {
+                    result.push(token);
+                }
+                continue;
+            }
+            LexItem::Decl(ConfigKeyword::Lease) if depth == 0 => in_lease = true,
+            LexItem::Decl(_) if depth == 0 => in_lease = false,
+            LexItem::Paren('{') => depth += 1,
+            LexItem::Paren('}') => depth -= 1,
+            _ => (),
+        }
+        result.push(token);
+    }
+
+    result
+}
+
+fn parse_config(
+    tokens: Vec<(LexItem, Span)>,
+    end: Span,
+    options: &ParserOptions,
+) -> Result<ParserResult, ParseError> {
+    let header = tokens
+        .iter()
+        .map_while(|t| match &t.0 {
+            LexItem::Comment(c) => Some(c.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let (tokens, spans): (Vec<LexItem>, Vec<Span>) =
+        filter_comments(tokens, options.preserve_comments)
+            .into_iter()
+            .unzip();
+    let mut ctx = ParseContext {
+        options: options.clone(),
+        diagnostics: Vec::new(),
+        tokens: &tokens,
+        spans: &spans,
+    };
+    let mut result = ParserResult {
+        leases: Leases::new(),
+        server_duid: None,
+        authoring_byte_order: None,
+        hosts: Vec::new(),
+        groups: Vec::new(),
+        classes: Vec::new(),
+        ias: Vec::new(),
+        file_info: FileInfo::from_comments(header),
+        diagnostics: Vec::new(),
+    };
 
     let mut it = tokens.iter().peekable();
+    while let Some(&start) = it.peek() {
+        let e = match parse_declaration(&mut it, &mut ctx, &mut result) {
+            Ok(()) => continue,
+            Err(e) => e,
+        };
+        // errors are reported at the token the parser stopped on
+        let span = it.peek().and_then(|t| ctx.span_of(t)).unwrap_or(end);
+        let e = e.at(span);
+        if !ctx.options.recover || matches!(e, ParseError::LimitExceeded { .. }) {
+            return Err(e);
+        }
 
-    while let Some(token) = it.peek() {
-        match token {
-            LexItem::Decl(ConfigKeyword::Lease) => {
-                if lease != Lease::new() {
-                    leases.push(lease.clone());
-                }
+        ctx.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            message: format!("Skipped malformed declaration: {}", e.message()),
+            span: e.span(),
+        });
+        // resume at the first lease declaration following the start of the
+        // failed one, which may have consumed it
+        let from = ctx.index_of(start).map_or(tokens.len(), |i| i + 1);
+        let next = tokens[from..]
+            .iter()
+            .position(|t| t == &LexItem::Decl(ConfigKeyword::Lease))
+            .map_or(tokens.len(), |i| from + i);
+        it = tokens[next..].iter().peekable();
+    }
 
-                let mut lease = Lease::new();
-                // ip-address
-                it.next();
-                lease.ip = it.peek().expect("IP address expected").to_string();
-
-                // left curly brace
-                it.next();
-                assert_eq!(it.peek().unwrap().to_owned(), &LexItem::Paren('{'));
-
-                // statements for the lease
-                it.next();
-                parse_lease(&mut lease, &mut it)?;
-
-                // right curly brace
-                if it.peek().is_none() || it.peek().unwrap().to_owned() != &LexItem::Paren('}') {
-                    return Err(format!(
-                        "Expected end of section with '}}', got '{:?}'",
-                        it.peek(),
-                    ));
-                }
+    result.diagnostics = ctx.diagnostics;
+    Ok(result)
+}
 
-                leases.push(lease.clone());
-                it.next();
+/// Parses one top level declaration, the iterator is left pointing at the
+/// token following it
+fn parse_declaration<'l, T: Iterator<Item = &'l LexItem> + Clone>(
+    it: &mut Peekable<T>,
+    ctx: &mut ParseContext,
+    result: &mut ParserResult,
+) -> Result<(), ParseError> {
+    let token = match it.peek() {
+        Some(token) => token,
+        None => return Ok(()),
+    };
+    match token {
+        LexItem::Decl(ConfigKeyword::Lease) => {
+            if let Some(max) = ctx.options.limits.max_leases {
+                if result.leases.len() >= max {
+                    return Err(ParseError::limit(format!(
+                        "More than {} leases in the input",
+                        max
+                    )));
+                }
             }
-            _ => {
-                return Err(format!("Unexpected {:?}", it.peek()));
+            let ip = parse_declaration_name(it, "lease")?;
+            let mut lease = Lease::with_ip(
+                ip.parse::<IpAddr>()
+                    .map_err(|_| format!("'{}' is not a valid lease address", ip))?,
+            );
+            parse_lease(&mut lease, it, ctx)?;
+            parse_declaration_end(it)?;
+
+            result.leases.push(lease);
+        }
+        LexItem::Decl(ConfigKeyword::Host) => {
+            let mut host = Host::new(unquote(parse_declaration_name(it, "host")?));
+            parse_host(&mut host, it)?;
+            parse_declaration_end(it)?;
+
+            result.hosts.push(host);
+        }
+        LexItem::Decl(ConfigKeyword::Group) | LexItem::Decl(ConfigKeyword::Subgroup) => {
+            let subgroup = *token == &LexItem::Decl(ConfigKeyword::Subgroup);
+            let mut group = Group::new(unquote(parse_declaration_name(it, "group")?));
+            group.subgroup = subgroup;
+            parse_group(&mut group, it)?;
+            parse_declaration_end(it)?;
+
+            result.groups.push(group);
+        }
+        LexItem::Decl(ConfigKeyword::Class) => {
+            let mut class = Class::new(unquote(parse_declaration_name(it, "class")?));
+            parse_class(&mut class, it)?;
+            parse_declaration_end(it)?;
+
+            result.classes.push(class);
+        }
+        LexItem::Decl(ConfigKeyword::Subclass) => {
+            // class name and subclass data
+            it.next();
+            let mut class = Class::new(unquote(
+                it.peek()
+                    .ok_or_else(|| ParseError::eof("Subclass name expected"))?
+                    .to_string(),
+            ));
+            it.next();
+            class.subclass.replace(unquote(
+                it.peek()
+                    .ok_or_else(|| ParseError::eof("Subclass data expected"))?
+                    .to_string(),
+            ));
+
+            // subclasses without statements have no block
+            it.next();
+            match it.peek() {
+                Some(LexItem::Endl) => {
+                    it.next();
+                }
+                Some(LexItem::Paren('{')) => {
+                    it.next();
+                    parse_class(&mut class, it)?;
+                    parse_declaration_end(it)?;
+                }
+                t => {
+                    return Err(ParseError::unexpected(format!(
+                        "Expected '{{' or semicolon after subclass data, got '{:?}'",
+                        t
+                    )))
+                }
             }
+
+            result.classes.push(class);
         }
+        LexItem::Decl(ConfigKeyword::IaNa)
+        | LexItem::Decl(ConfigKeyword::IaTa)
+        | LexItem::Decl(ConfigKeyword::IaPd) => {
+            let kind = match token {
+                LexItem::Decl(ConfigKeyword::IaTa) => IaKind::Ta,
+                LexItem::Decl(ConfigKeyword::IaPd) => IaKind::Pd,
+                _ => IaKind::Na,
+            };
+            // the id is kept escaped, as it holds binary data
+            let id = parse_declaration_name(it, "ia")?;
+            let id = match id.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                Some(inner) => inner.to_owned(),
+                None => id,
+            };
+            let mut ia = Ia::new(kind, id);
+            parse_ia(&mut ia, it, ctx)?;
+            parse_declaration_end(it)?;
+
+            result.ias.push(ia);
+        }
+        LexItem::Decl(ConfigKeyword::ServerDuid) => {
+            result.server_duid.replace(parse_value(it, "server-duid")?);
+            it.next();
+        }
+        LexItem::Decl(ConfigKeyword::AuthoringByteOrder) => {
+            result
+                .authoring_byte_order
+                .replace(parse_value(it, "authoring-byte-order")?);
+            it.next();
+        }
+        _ if ctx.options.mode == ParseMode::Lenient => {
+            let first = *token;
+            let statement = match first {
+                LexItem::Paren('}') | LexItem::Endl => first.to_string(),
+                _ => parse_raw_statement(it)?,
+            };
+            ctx.report(
+                Severity::Warning,
+                first,
+                format!("Skipped unknown declaration '{}'", statement),
+            );
+            it.next();
+        }
+        _ => {
+            return Err(ParseError::unexpected(format!(
+                "Unexpected {:?}",
+                it.peek()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the name of a `<keyword> name { ... }` declaration, as written. The
+/// iterator is expected to point at the keyword and is left pointing at the
+/// first token after the opening brace.
+fn parse_declaration_name<'l, T: Iterator<Item = &'l LexItem>>(
+    it: &mut Peekable<T>,
+    what: &str,
+) -> Result<String, ParseError> {
+    it.next();
+    let name = match it.peek() {
+        Some(t) => t.to_string(),
+        None => return Err(ParseError::eof(format!("Name of the {} expected", what))),
+    };
+
+    it.next();
+    if it.peek() != Some(&&LexItem::Paren('{')) {
+        return Err(ParseError::unexpected(format!(
+            "Expected '{{' after {} name, got '{:?}'",
+            what,
+            it.peek()
+        )));
+    }
+
+    it.next();
+    Ok(name)
+}
+
+/// Checks that a declaration block is closed and moves past the closing brace
+fn parse_declaration_end<'l, T: Iterator<Item = &'l LexItem>>(
+    it: &mut Peekable<T>,
+) -> Result<(), ParseError> {
+    let message = format!("Expected end of section with '}}', got '{:?}'", it.peek());
+    match it.peek() {
+        Some(LexItem::Paren('}')) => (),
+        Some(_) => return Err(ParseError::unexpected(message)),
+        None => return Err(ParseError::unterminated(message)),
     }
 
-    Ok(ParserResult { leases: leases })
+    it.next();
+    Ok(())
 }
 
-pub fn parse<S>(input: S) -> Result<ParserResult, String>
+pub fn parse<S>(input: S) -> Result<ParserResult, ParseError>
 where
     S: Into<String>,
 {
-    let tokens = lex(input).unwrap();
-    return parse_config(tokens);
+    parse_with_options(input, &ParserOptions::default())
+}
+
+pub fn parse_with_options<S>(input: S, options: &ParserOptions) -> Result<ParserResult, ParseError>
+where
+    S: Into<String>,
+{
+    let input = input.into();
+    parse_text(&input, options, &options.limits)
+}
+
+fn parse_text(
+    input: &str,
+    options: &ParserOptions,
+    limits: &Limits,
+) -> Result<ParserResult, ParseError> {
+    let tokens = lex_spanned_with_limits(input, limits)?;
+    parse_config(tokens, end_span(input), options)
+}
+
+/// Reads and parses a leases file. Bytes that are not valid UTF-8 are
+/// replaced rather than rejected, dhcpd copies some client supplied values
+/// as is.
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ParserResult, ParseError> {
+    parse_file_with_options(path, &ParserOptions::default())
+}
+
+pub fn parse_file_with_options<P: AsRef<Path>>(
+    path: P,
+    options: &ParserOptions,
+) -> Result<ParserResult, ParseError> {
+    let path = path.as_ref();
+    let io_error = |source| ParseError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let file = File::open(path).map_err(io_error)?;
+    // reads one byte past the limit, to tell files over it without reading
+    // all of them
+    let max_size = options.limits.max_input_size;
+    let mut bytes = Vec::new();
+    file.take(max_size.map_or(u64::MAX, |max| (max as u64).saturating_add(1)))
+        .read_to_end(&mut bytes)
+        .map_err(io_error)?;
+    if let Some(max) = max_size {
+        if bytes.len() > max {
+            return Err(ParseError::limit(format!(
+                "{} is over the limit of {} bytes",
+                path.display(),
+                max
+            )));
+        }
+    }
+    let content = String::from_utf8_lossy(&bytes);
+
+    // the size was checked on the raw bytes, replacing invalid UTF-8 may
+    // make the text longer
+    let limits = Limits {
+        max_input_size: None,
+        ..options.limits
+    };
+    parse_text(content.trim_start_matches('\u{feff}'), options, &limits)
 }
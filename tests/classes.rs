@@ -0,0 +1,54 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::parser;
+
+#[test]
+fn dynamic_class_test() {
+    let res = parser::parse(
+        "
+    class \"voip\" {
+        dynamic;
+        lease limit 10;
+    }
+
+    subclass \"voip\" \"SIP\" {
+        dynamic;
+    }
+
+    subclass \"voip\" 1:0:11:22:33:44:55;
+
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        billing subclass \"voip\" \"SIP\";
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(res.classes.len(), 3);
+
+    let class = &res.classes[0];
+    assert_eq!(class.name, "voip");
+    assert!(class.dynamic);
+    assert!(class.subclass.is_none());
+    assert_eq!(class.statements, vec!["lease limit 10"]);
+
+    let class = &res.classes[1];
+    assert_eq!(class.name, "voip");
+    assert!(class.dynamic);
+    assert_eq!(class.subclass.as_ref().unwrap(), "SIP");
+
+    let class = &res.classes[2];
+    assert!(!class.dynamic);
+    assert_eq!(class.subclass.as_ref().unwrap(), "1:0:11:22:33:44:55");
+
+    assert_eq!(
+        res.leases[0]
+            .billing
+            .as_ref()
+            .unwrap()
+            .subclass
+            .as_ref()
+            .unwrap(),
+        "SIP"
+    );
+}
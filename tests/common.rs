@@ -1,6 +1,12 @@
 extern crate dhcpd_parser;
 
+use crate::dhcpd_parser::common::format_hex_bytes;
+use crate::dhcpd_parser::common::parse_hex_bytes;
+use crate::dhcpd_parser::common::unescape_bytes;
 use crate::dhcpd_parser::common::Date;
+use crate::dhcpd_parser::common::DateRange;
+use crate::dhcpd_parser::common::MacAddr;
+use crate::dhcpd_parser::common::Uid;
 
 #[test]
 fn date_rfc3339() {
@@ -31,11 +37,291 @@ fn date_rfc3339() {
     );
 
     assert_eq!(
-        Date::from_rfc3339(7, "2015-01-01T21:21:21Z").unwrap_err(),
+        Date::from_rfc3339(7, "2015-01-01T21:21:21Z")
+            .unwrap_err()
+            .to_string(),
         "Weekday should be a number between 0 and 6. 7 is not",
     );
     assert_eq!(
-        Date::from_rfc3339(1, "T").unwrap_err(),
+        Date::from_rfc3339(1, "T").unwrap_err().to_string(),
         "This doesn\'t seem like a correct RFC3339 date: \"T\"",
     );
 }
+
+#[test]
+fn unescape_bytes_test() {
+    assert_eq!(
+        unescape_bytes("\\001\\014\\316abc").unwrap(),
+        vec![1, 12, 206, b'a', b'b', b'c']
+    );
+    assert_eq!(
+        unescape_bytes("\\\"quoted\\\" \\\\").unwrap(),
+        b"\"quoted\" \\".to_vec()
+    );
+    assert!(unescape_bytes("\\777").is_err());
+    assert!(unescape_bytes("abc\\").is_err());
+}
+
+#[test]
+fn hex_bytes_test() {
+    assert_eq!(parse_hex_bytes("1:c:CE:ff").unwrap(), vec![1, 12, 206, 255]);
+    assert_eq!(format_hex_bytes(&[1, 12, 206, 255]), "01:0c:ce:ff");
+    assert!(parse_hex_bytes("1::2").is_err());
+    assert!(parse_hex_bytes("Client1").is_err());
+}
+
+#[test]
+fn date_unix_timestamp() {
+    assert_eq!(
+        Date::from_unix_timestamp(0),
+        Date {
+            weekday: 4,
+            ..Date::new()
+        }
+    );
+    assert_eq!(
+        Date::from_unix_timestamp(951827696),
+        Date {
+            weekday: 2,
+            year: 2000,
+            month: 2,
+            day: 29,
+            hour: 12,
+            minute: 34,
+            second: 56,
+        }
+    );
+    assert_eq!(
+        Date::from_unix_timestamp(-1),
+        Date {
+            weekday: 3,
+            year: 1969,
+            month: 12,
+            day: 31,
+            hour: 23,
+            minute: 59,
+            second: 59,
+        }
+    );
+}
+
+#[test]
+fn mac_addr_test() {
+    let mac = "0:1B:c:ff:3:4".parse::<MacAddr>().unwrap();
+    assert_eq!(mac.to_string(), "00:1b:0c:ff:03:04");
+    assert_eq!(mac.as_bytes(), &[0x00, 0x1b, 0x0c, 0xff, 0x03, 0x04]);
+    assert_eq!(mac, "00:1b:0c:FF:03:04".parse::<MacAddr>().unwrap());
+    assert_eq!(mac, "00-1B-0C-FF-03-04");
+    assert!(mac != "00:1b:0c:ff:03:05");
+    assert!(mac != "not a mac");
+
+    assert!("".parse::<MacAddr>().is_err());
+    assert!("00:1b:zz".parse::<MacAddr>().is_err());
+    assert!("001b0c".parse::<MacAddr>().is_err());
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn date_chrono_test() {
+    use std::convert::TryFrom;
+
+    let date = Date::from("2", "2019/01/01", "22:30:05").unwrap();
+    let naive = chrono::NaiveDateTime::try_from(date).unwrap();
+    assert_eq!(naive.to_string(), "2019-01-01 22:30:05");
+    let back: Date = naive.into();
+    assert_eq!(back, date);
+
+    let utc = chrono::DateTime::<chrono::Utc>::try_from(date).unwrap();
+    assert_eq!(utc.timestamp(), 1546381805);
+    let back: Date = utc.into();
+    assert_eq!(back, date);
+
+    let mut invalid = date;
+    invalid.month = 2;
+    invalid.day = 30;
+    assert!(chrono::NaiveDateTime::try_from(invalid).is_err());
+}
+
+#[test]
+fn date_unix_timestamp_roundtrip() {
+    for ts in &[0, 1546381805, -86401, 951782400, 4102444799] {
+        assert_eq!(
+            Date::from_unix_timestamp(*ts).to_unix_timestamp(),
+            Some(*ts)
+        );
+    }
+    assert_eq!(
+        Date::from("2", "2019/01/01", "22:30:05")
+            .unwrap()
+            .to_unix_timestamp(),
+        Some(1546381805)
+    );
+
+    let mut far = Date::new();
+    far.year = i64::MAX / 365;
+    assert_eq!(far.to_unix_timestamp(), None);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn date_time_test() {
+    let date = Date::from("2", "2019/01/01", "22:30:05").unwrap();
+    let dt = time::OffsetDateTime::from(date);
+    assert_eq!(dt.unix_timestamp(), 1546381805);
+    let back: Date = dt.into();
+    assert_eq!(back, date);
+
+    let offset = dt.to_offset(time::UtcOffset::from_hms(2, 0, 0).unwrap());
+    let back: Date = offset.into();
+    assert_eq!(back, date);
+
+    let back: Date = time::PrimitiveDateTime::new(dt.date(), dt.time()).into();
+    assert_eq!(back, date);
+}
+
+#[test]
+fn date_display_test() {
+    let date = Date::from("2", "2019/01/01", "08:05:09").unwrap();
+    assert_eq!(date.to_string(), "2019-01-01T08:05:09Z");
+    assert_eq!(date.to_dhcpd_string(), "2 2019/01/01 08:05:09");
+    assert_eq!(Date::new().to_string(), "1970-01-01T00:00:00Z");
+}
+
+#[test]
+fn date_bounds_test() {
+    assert!(Date::from("3", "2020/12/31", "00:00:00").is_ok());
+    assert!(Date::from("3", "2020/13/01", "00:00:00").is_err());
+    assert!(Date::from("3", "2020/00/01", "00:00:00").is_err());
+    assert!(Date::from("3", "2020/01/32", "00:00:00").is_err());
+    assert!(Date::from("3", "2020/01/00", "00:00:00").is_err());
+    assert!(Date::from("3", "10000/01/01", "00:00:00").is_err());
+}
+
+#[test]
+fn date_from_str_test() {
+    let date: Date = "2024-05-01T12:00:00Z".parse().unwrap();
+    assert_eq!(date.to_dhcpd_string(), "3 2024/05/01 12:00:00");
+    assert_eq!("2024-05-01T12:00:00".parse::<Date>().unwrap(), date);
+    assert_eq!("2024-05-01T12:00:00.250Z".parse::<Date>().unwrap(), date);
+    assert_eq!("2024-05-01T14:00:00+02:00".parse::<Date>().unwrap(), date);
+    assert_eq!(
+        "2024-05-01T23:30:00-11:30"
+            .parse::<Date>()
+            .unwrap()
+            .to_string(),
+        "2024-05-02T11:00:00Z"
+    );
+    assert_eq!(
+        "2024-05-01T17:30:00+0530"
+            .parse::<Date>()
+            .unwrap()
+            .to_string(),
+        "2024-05-01T12:00:00Z"
+    );
+    assert_eq!("2024-05-01T17:00:00+05".parse::<Date>().unwrap().hour, 12);
+    assert_eq!("3 2024/05/01 12:00:00".parse::<Date>().unwrap(), date);
+
+    assert!("".parse::<Date>().is_err());
+    assert!("2024-05-01".parse::<Date>().is_err());
+    assert!("2024-02-30T12:00:00Z".parse::<Date>().is_err());
+    assert!("2024-05-01T24:00:00Z".parse::<Date>().is_err());
+    assert!("2024-05-01T12:00:00+xx".parse::<Date>().is_err());
+    assert!("2024-05-01T12:00:00+99:00".parse::<Date>().is_err());
+    assert!("2024-05-01T12:00:00+05:60".parse::<Date>().is_err());
+    assert!("2024-05-01T12:00:00+530".parse::<Date>().is_err());
+    assert!("x 2024/05/01 12:00:00".parse::<Date>().is_err());
+}
+
+#[test]
+fn date_ordering_test() {
+    let base = Date::from("2", "2019/01/01", "10:10:10").unwrap();
+    let fields: Vec<fn(&mut Date)> = vec![
+        |d| d.year += 1,
+        |d| d.month += 1,
+        |d| d.day += 1,
+        |d| d.hour += 1,
+        |d| d.minute += 1,
+        |d| d.second += 1,
+    ];
+
+    // every field orders, more significant fields take precedence
+    for (i, bump) in fields.iter().enumerate() {
+        let mut later = base;
+        bump(&mut later);
+        assert!(later > base);
+        assert!(base < later);
+        assert_eq!(base.max(later), later);
+        assert_eq!(Date::min(base, later), base);
+
+        for lesser in &fields[i + 1..] {
+            let mut other = base;
+            for _ in 0..5 {
+                lesser(&mut other);
+            }
+            assert!(later > other);
+        }
+    }
+
+    // the weekday does not take part in comparisons
+    let mut other_weekday = base;
+    other_weekday.weekday = 5;
+    assert_eq!(base, other_weekday);
+    assert_eq!(base.cmp(&other_weekday), std::cmp::Ordering::Equal);
+
+    let mut dates = [
+        "2020-01-01T00:00:00Z".parse::<Date>().unwrap(),
+        "2019-12-31T23:59:59Z".parse::<Date>().unwrap(),
+        "2020-01-01T00:00:01Z".parse::<Date>().unwrap(),
+    ];
+    dates.sort();
+    assert_eq!(dates[0].to_string(), "2019-12-31T23:59:59Z");
+    assert_eq!(dates[2].to_string(), "2020-01-01T00:00:01Z");
+}
+
+#[test]
+fn date_range_test() {
+    let d = |s: &str| s.parse::<Date>().unwrap();
+    let range = DateRange::new(
+        Some(d("2019-01-01T10:00:00Z")),
+        Some(d("2019-01-01T11:00:00Z")),
+    );
+
+    assert!(range.contains(&d("2019-01-01T10:00:00Z")));
+    assert!(range.contains(&d("2019-01-01T10:30:00Z")));
+    assert!(range.contains(&d("2019-01-01T11:00:00Z")));
+    assert!(!range.contains(&d("2019-01-01T09:59:59Z")));
+    assert!(!range.contains(&d("2019-01-01T11:00:01Z")));
+
+    let open_end = DateRange::new(Some(d("2019-01-01T10:00:00Z")), None);
+    assert!(open_end.contains(&d("2100-01-01T00:00:00Z")));
+    assert!(!open_end.contains(&d("2000-01-01T00:00:00Z")));
+    assert!(DateRange::default().contains(&d("2000-01-01T00:00:00Z")));
+
+    assert!(range.overlaps(&open_end));
+    assert!(range.overlaps(&DateRange::new(None, Some(d("2019-01-01T10:00:00Z")))));
+    assert!(!range.overlaps(&DateRange::new(Some(d("2019-01-01T11:00:01Z")), None)));
+    assert!(!range.overlaps(&DateRange::new(
+        Some(d("2019-01-01T08:00:00Z")),
+        Some(d("2019-01-01T09:00:00Z"))
+    )));
+}
+
+#[test]
+fn uid_test() {
+    let quoted = "\"\\001\\000\\014)\"".parse::<Uid>().unwrap();
+    let hex = "1:0:C:29".parse::<Uid>().unwrap();
+
+    assert_eq!(quoted.as_bytes(), &[0x01, 0x00, 0x0c, 0x29]);
+    assert_eq!(quoted, hex);
+    assert_eq!(quoted.as_str(), "\"\\001\\000\\014)\"");
+    assert_eq!(hex.as_str(), "1:0:C:29");
+    assert_eq!(hex.to_string(), "01:00:0c:29");
+    assert_eq!(hex, "01:00:0c:29");
+    assert_eq!(Uid::new(vec![1, 0, 0x0c, 0x29]), quoted);
+
+    let word = "Client1".parse::<Uid>().unwrap();
+    assert_eq!(word.as_bytes(), b"Client1");
+    assert_ne!(word, hex);
+
+    assert!("\"dangling\\\"".parse::<Uid>().is_err());
+}
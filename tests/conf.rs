@@ -0,0 +1,830 @@
+extern crate dhcpd_parser;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+
+use crate::dhcpd_parser::conf;
+use crate::dhcpd_parser::conf::ClassMatch;
+use crate::dhcpd_parser::conf::DdnsUpdateStyle;
+use crate::dhcpd_parser::conf::DhcpdConfig;
+use crate::dhcpd_parser::conf::FailoverRole;
+use crate::dhcpd_parser::conf::OptionType;
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::parser::LeasesMethods;
+use crate::dhcpd_parser::parser::ParseError;
+
+#[test]
+fn subnet_test() {
+    let res = conf::parse(
+        "
+    # global parameters
+    default-lease-time 600;
+    option domain-name \"example.org\";
+
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        range 10.0.0.10 10.0.0.100;
+        range dynamic-bootp 10.0.0.200;
+        option routers 10.0.0.1;
+        option domain-name-servers 10.0.0.2, 10.0.0.3;
+        max-lease-time 7200;
+    }
+
+    subnet 10.0.1.0 netmask 255.255.255.0 {
+    }",
+    );
+
+    let config = res.unwrap();
+    assert_eq!(config.global.parameters["default-lease-time"], "600");
+    assert_eq!(config.global.options["domain-name"], "\"example.org\"");
+    assert_eq!(config.global.subnets.len(), 2);
+
+    let subnet = &config.global.subnets[0];
+    assert_eq!(subnet.network, Ipv4Addr::new(10, 0, 0, 0));
+    assert_eq!(subnet.netmask, Ipv4Addr::new(255, 255, 255, 0));
+    assert!(subnet.contains(Ipv4Addr::new(10, 0, 0, 42)));
+    assert!(!subnet.contains(Ipv4Addr::new(10, 0, 1, 42)));
+    assert_eq!(subnet.scope.options["routers"], "10.0.0.1");
+    assert_eq!(
+        subnet.scope.options["domain-name-servers"],
+        "10.0.0.2, 10.0.0.3"
+    );
+    assert_eq!(subnet.scope.parameters["max-lease-time"], "7200");
+
+    assert_eq!(subnet.scope.ranges.len(), 2);
+    assert!(!subnet.scope.ranges[0].dynamic_bootp);
+    assert!(subnet.scope.ranges[0].contains(Ipv4Addr::new(10, 0, 0, 50)));
+    assert!(!subnet.scope.ranges[0].contains(Ipv4Addr::new(10, 0, 0, 150)));
+    assert!(subnet.scope.ranges[1].dynamic_bootp);
+    assert_eq!(subnet.scope.ranges[1].start, subnet.scope.ranges[1].end);
+
+    assert!(config.global.subnets[1].scope.ranges.is_empty());
+}
+
+#[test]
+fn invalid_subnet_test() {
+    assert!(conf::parse("subnet 10.0.0.0 netmask 255.255.255.0 {").is_err());
+    assert!(conf::parse("subnet 10.0.0 netmask 255.255.255.0 { }").is_err());
+    assert!(conf::parse("subnet 10.0.0.0 { }").is_err());
+    assert!(conf::parse("subnet 10.0.0.0 netmask 255.255.255.0 { range 10.0.0.1 foo; }").is_err());
+    assert!(conf::parse("}").is_err());
+}
+
+#[test]
+fn host_test() {
+    let res = conf::parse(
+        "
+    host printer {
+        hardware ethernet 00:11:22:33:44:55;
+        fixed-address 10.0.0.5;
+        option host-name \"printer\";
+    }
+
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        host \"phone\" {
+            hardware ethernet 00:11:22:33:44:66;
+            fixed-address 10.0.0.6, phone.example.org;
+            default-lease-time 60;
+        }
+    }",
+    );
+
+    let config = res.unwrap();
+    let host = &config.global.hosts[0];
+    assert_eq!(host.name, "printer");
+    assert_eq!(
+        host.hardware.as_ref().unwrap().mac.as_ref().unwrap(),
+        "00:11:22:33:44:55"
+    );
+    assert_eq!(host.fixed_addresses, vec!["10.0.0.5"]);
+    assert_eq!(host.host_name(), Some("printer".to_owned()));
+
+    let host = &config.global.subnets[0].scope.hosts[0];
+    assert_eq!(host.name, "phone");
+    assert_eq!(host.fixed_addresses, vec!["10.0.0.6", "phone.example.org"]);
+    assert_eq!(host.scope.parameters["default-lease-time"], "60");
+    assert!(host.host_name().is_none());
+
+    assert!(conf::parse("host printer { hardware ethernet 00:11:22:33:44:55 }").is_err());
+}
+
+#[test]
+fn group_test() {
+    let res = conf::parse(
+        "
+    default-lease-time 600;
+    option domain-name \"example.org\";
+
+    group {
+        default-lease-time 3600;
+        option routers 10.0.0.1;
+
+        host printer {
+            fixed-address 10.0.0.5;
+        }
+
+        group \"phones\" {
+            option domain-name \"phones.example.org\";
+            host phone {
+                max-lease-time 60;
+            }
+        }
+    }
+
+    host laptop {
+    }",
+    );
+
+    let config = res.unwrap();
+    assert_eq!(config.global.groups.len(), 1);
+    let group = &config.global.groups[0];
+    assert!(group.name.is_none());
+    assert_eq!(group.scope.hosts[0].name, "printer");
+    assert_eq!(group.scope.groups[0].name, Some("phones".to_owned()));
+
+    let printer = config.host_parameters("printer").unwrap();
+    assert_eq!(printer.parameters["default-lease-time"], "3600");
+    assert_eq!(printer.options["routers"], "10.0.0.1");
+    assert_eq!(printer.options["domain-name"], "\"example.org\"");
+
+    let phone = config.host_parameters("phone").unwrap();
+    assert_eq!(phone.parameters["default-lease-time"], "3600");
+    assert_eq!(phone.parameters["max-lease-time"], "60");
+    assert_eq!(phone.options["domain-name"], "\"phones.example.org\"");
+
+    let laptop = config.host_parameters("laptop").unwrap();
+    assert_eq!(laptop.parameters["default-lease-time"], "600");
+    assert!(!laptop.options.contains_key("routers"));
+
+    assert!(config.host_parameters("unknown").is_none());
+}
+
+#[test]
+fn pool_test() {
+    let res = conf::parse(
+        "
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        option routers 10.0.0.1;
+
+        pool {
+            range 10.0.0.10 10.0.0.19;
+            range 10.0.0.30;
+            allow members of \"voip\";
+            deny unknown-clients;
+            failover peer \"dhcp-failover\";
+        }
+
+        pool {
+            range 10.0.0.100 10.0.0.199;
+            allow unknown-clients;
+        }
+    }",
+    );
+
+    let config = res.unwrap();
+    let pools = &config.global.subnets[0].scope.pools;
+    assert_eq!(pools.len(), 2);
+
+    assert_eq!(pools[0].size(), 11);
+    assert!(pools[0].contains(Ipv4Addr::new(10, 0, 0, 15)));
+    assert!(pools[0].contains(Ipv4Addr::new(10, 0, 0, 30)));
+    assert!(!pools[0].contains(Ipv4Addr::new(10, 0, 0, 20)));
+    assert_eq!(pools[0].allow, vec!["members of \"voip\""]);
+    assert_eq!(pools[0].deny, vec!["unknown-clients"]);
+    assert_eq!(pools[0].failover_peer, Some("dhcp-failover".to_owned()));
+
+    assert_eq!(pools[1].size(), 100);
+    assert!(pools[1].deny.is_empty());
+    assert!(pools[1].failover_peer.is_none());
+
+    assert!(conf::parse("pool { failover \"peer\"; }").is_err());
+}
+
+#[test]
+fn shared_network_test() {
+    let res = conf::parse(
+        "
+    shared-network \"floor-2\" {
+        option domain-name-servers 10.0.0.2;
+
+        subnet 10.0.2.0 netmask 255.255.255.0 {
+            range 10.0.2.10 10.0.2.100;
+        }
+
+        subnet 10.0.3.0 netmask 255.255.255.0 {
+            host printer {
+                fixed-address 10.0.3.5;
+            }
+        }
+
+        pool {
+            range 10.0.3.10 10.0.3.20;
+        }
+    }",
+    );
+
+    let config = res.unwrap();
+    assert!(config.global.subnets.is_empty());
+    let network = &config.global.shared_networks[0];
+    assert_eq!(network.name, "floor-2");
+    assert_eq!(network.scope.subnets.len(), 2);
+    assert_eq!(network.scope.pools.len(), 1);
+    assert_eq!(network.scope.subnets[0].network, Ipv4Addr::new(10, 0, 2, 0));
+
+    let printer = config.host_parameters("printer").unwrap();
+    assert_eq!(printer.options["domain-name-servers"], "10.0.0.2");
+
+    assert!(conf::parse("shared-network { }").is_err());
+}
+
+#[test]
+fn class_test() {
+    let res = conf::parse(
+        "
+    class \"voip\" {
+        match if substring(option vendor-class-identifier, 0, 4) = \"SIP\";
+        option tftp-server-name \"10.0.0.9\";
+    }
+
+    class \"clients\" {
+        match hardware;
+        spawn with option agent.circuit-id;
+        lease limit 4;
+    }
+
+    subclass \"clients\" 1:00:11:22:33:44:55;
+    subclass \"clients\" 1:00:11:22:33:44:66 {
+        option routers 10.0.0.254;
+    }",
+    );
+
+    let config = res.unwrap();
+    let classes = &config.global.classes;
+    assert_eq!(classes[0].name, "voip");
+    assert_eq!(
+        classes[0].matches,
+        Some(ClassMatch::If(vec![
+            "substring".to_owned(),
+            "(".to_owned(),
+            "option".to_owned(),
+            "vendor-class-identifier,".to_owned(),
+            "0,".to_owned(),
+            "4".to_owned(),
+            ")".to_owned(),
+            "=".to_owned(),
+            "\"SIP\"".to_owned(),
+        ]))
+    );
+    assert_eq!(classes[0].scope.options["tftp-server-name"], "\"10.0.0.9\"");
+
+    assert_eq!(
+        classes[1].matches,
+        Some(ClassMatch::Value(vec!["hardware".to_owned()]))
+    );
+    assert_eq!(
+        classes[1].spawn_with,
+        Some(vec!["option".to_owned(), "agent.circuit-id".to_owned()])
+    );
+    assert_eq!(classes[1].lease_limit, Some(4));
+
+    let subclasses = &config.global.subclasses;
+    assert_eq!(subclasses.len(), 2);
+    assert_eq!(subclasses[0].class, "clients");
+    assert_eq!(subclasses[0].data, "1:00:11:22:33:44:55");
+    assert_eq!(subclasses[1].scope.options["routers"], "10.0.0.254");
+
+    assert!(conf::parse("class \"voip\" { match if; }").is_err());
+    assert!(conf::parse("class \"voip\" { lease limit many; }").is_err());
+}
+
+#[test]
+fn option_definitions_test() {
+    let res = conf::parse(
+        "
+    option space ubnt;
+    option space vendor code width 2 length width 2 hash size 17;
+    option ubnt.unifi-address code 1 = ip-address;
+    option classless-routes code 121 = array of unsigned integer 8;
+    option sip-servers code 120 = { ip-address, text };
+    option search-domains code 119 = domain-list compressed;
+
+    class \"unifi\" {
+        option ubnt.unifi-address 10.0.0.2;
+    }",
+    );
+
+    let config = res.unwrap();
+    let spaces = &config.global.option_spaces;
+    assert_eq!(spaces.len(), 2);
+    assert_eq!(spaces[0].name, "ubnt");
+    assert!(spaces[0].code_width.is_none());
+    assert_eq!(spaces[1].code_width, Some(2));
+    assert_eq!(spaces[1].length_width, Some(2));
+    assert_eq!(spaces[1].hash_size, Some(17));
+
+    let unifi = config.option_definition("ubnt.unifi-address").unwrap();
+    assert_eq!(unifi.space, Some("ubnt".to_owned()));
+    assert_eq!(unifi.name, "unifi-address");
+    assert_eq!(unifi.code, 1);
+    assert_eq!(unifi.option_type, OptionType::IpAddress);
+
+    let routes = config.option_definition("classless-routes").unwrap();
+    assert!(routes.space.is_none());
+    assert_eq!(
+        routes.option_type,
+        OptionType::Array(Box::new(OptionType::Integer {
+            signed: false,
+            bits: 8
+        }))
+    );
+    assert_eq!(
+        config.option_definition("sip-servers").unwrap().option_type,
+        OptionType::Record(vec![OptionType::IpAddress, OptionType::Text])
+    );
+    assert_eq!(
+        config
+            .option_definition("search-domains")
+            .unwrap()
+            .option_type,
+        OptionType::DomainList
+    );
+    assert!(config.option_definition("unknown").is_none());
+
+    assert_eq!(
+        config.global.classes[0].scope.options["ubnt.unifi-address"],
+        "10.0.0.2"
+    );
+
+    assert!(conf::parse("option foo code bar = text;").is_err());
+    assert!(conf::parse("option foo code 1 = { text, ip-address;").is_err());
+    assert!(conf::parse("option space ubnt code width;").is_err());
+}
+
+#[test]
+fn global_parameters_test() {
+    let res = conf::parse(
+        "
+    default-lease-time 600;
+    max-lease-time 7200;
+    authoritative;
+    ddns-update-style interim;
+    log-facility local7;
+
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        not authoritative;
+        ddns-update-style sometimes;
+        min-lease-time forever;
+    }",
+    );
+
+    let config = res.unwrap();
+    let global = &config.global;
+    assert_eq!(global.default_lease_time(), Some(600));
+    assert_eq!(global.max_lease_time(), Some(7200));
+    assert!(global.min_lease_time().is_none());
+    assert_eq!(global.authoritative(), Some(true));
+    assert_eq!(global.ddns_update_style(), Some(DdnsUpdateStyle::Interim));
+    assert_eq!(global.parameters["log-facility"], "local7");
+    assert_eq!(
+        global.parameter::<String>("log-facility"),
+        Some("local7".to_owned())
+    );
+
+    let subnet = &global.subnets[0].scope;
+    assert_eq!(subnet.authoritative(), Some(false));
+    assert!(subnet.ddns_update_style().is_none());
+    assert!(subnet.min_lease_time().is_none());
+    assert!(subnet.default_lease_time().is_none());
+}
+
+#[test]
+fn repeated_parameters_test() {
+    let res = conf::parse(
+        "
+    allow unknown-clients;
+    allow bootp;
+    deny duplicates;
+    default-lease-time 600;
+    default-lease-time 700;
+
+    host printer {
+        deny bootp;
+        default-lease-time 60;
+    }",
+    );
+
+    let config = res.unwrap();
+    let global = &config.global;
+    assert_eq!(
+        global.parameters.get_all("allow"),
+        vec!["unknown-clients", "bootp"]
+    );
+    assert_eq!(global.parameters.get_all("deny"), vec!["duplicates"]);
+    assert_eq!(global.parameters["allow"], "bootp");
+    assert_eq!(global.default_lease_time(), Some(700));
+    assert_eq!(global.parameters.len(), 5);
+
+    let printer = config.host_parameters("printer").unwrap();
+    assert_eq!(
+        printer.parameters.get_all("allow"),
+        vec!["unknown-clients", "bootp"]
+    );
+    assert_eq!(printer.parameters.get_all("deny"), vec!["bootp"]);
+    assert_eq!(printer.parameters.get_all("default-lease-time"), vec!["60"]);
+}
+
+fn files(list: &[(&str, &str)]) -> HashMap<String, String> {
+    list.iter()
+        .map(|(p, c)| (p.to_string(), c.to_string()))
+        .collect()
+}
+
+#[test]
+fn include_test() {
+    let loader = files(&[
+        (
+            "/etc/dhcpd.conf",
+            "
+    default-lease-time 600;
+    include \"/etc/dhcpd/subnets.conf\";
+    group {
+        include \"/etc/dhcpd/hosts.conf\";
+    }",
+        ),
+        (
+            "/etc/dhcpd/subnets.conf",
+            "subnet 10.0.0.0 netmask 255.255.255.0 { range 10.0.0.10 10.0.0.20; }",
+        ),
+        (
+            "/etc/dhcpd/hosts.conf",
+            "# reservations\nhost printer { fixed-address 10.0.0.5; }",
+        ),
+    ]);
+
+    let config = conf::parse_with_includes("/etc/dhcpd.conf", &loader).unwrap();
+    assert_eq!(config.global.default_lease_time(), Some(600));
+    assert_eq!(config.global.subnets.len(), 1);
+    assert_eq!(config.global.groups[0].scope.hosts[0].name, "printer");
+    assert!(!config.global.parameters.contains_key("include"));
+
+    let config = conf::parse(&loader["/etc/dhcpd.conf"]).unwrap();
+    assert_eq!(
+        config.global.parameters["include"],
+        "\"/etc/dhcpd/subnets.conf\""
+    );
+}
+
+#[test]
+fn include_errors_test() {
+    let loader = files(&[
+        ("a.conf", "include \"b.conf\";"),
+        ("b.conf", "include \"a.conf\";"),
+        ("c.conf", "include \"missing.conf\";"),
+        ("d.conf", "include;"),
+    ]);
+
+    let err = conf::parse_with_includes("a.conf", &loader).unwrap_err();
+    assert!(err.to_string().contains("a.conf -> b.conf -> a.conf"));
+    let err = conf::parse_with_includes("c.conf", &loader).unwrap_err();
+    assert!(matches!(err, ParseError::Io { ref path, .. } if path.ends_with("missing.conf")));
+    assert!(conf::parse_with_includes("d.conf", &loader).is_err());
+    let err = conf::parse_with_includes("/nonexistent/dhcpd.conf", &conf::FsLoader).unwrap_err();
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn error_location_test() {
+    let err = conf::parse(
+        "default-lease-time 600;
+subnet 10.0.0.0 netmask 255.255.255.0 {
+  range 10.0.0.1 foo;
+}",
+    )
+    .unwrap_err();
+    assert!(matches!(err, ParseError::InvalidValue { .. }));
+    let span = err.span().unwrap();
+    assert_eq!((span.line, span.column), (3, 18));
+
+    let err = conf::parse("subnet 10.0.0.0 netmask 255.255.255.0 {").unwrap_err();
+    assert!(matches!(err, ParseError::UnexpectedToken { .. }));
+    assert_eq!(err.span().unwrap().line, 1);
+}
+
+#[test]
+fn conditional_test() {
+    let res = conf::parse(
+        "
+    if option vendor-class-identifier = \"PXEClient\" {
+        filename \"pxelinux.0\";
+    } elsif exists user-class {
+        filename \"ipxe.efi\";
+    } else if known {
+        option routers 10.0.0.1;
+    } else {
+        ignore booting;
+    }
+
+    if substring(hardware, 1, 3) = 00:11:22 {
+        log(info, \"matched\");
+    }
+    default-lease-time 600;",
+    );
+
+    let config = res.unwrap();
+    let conditionals = &config.global.conditionals;
+    assert_eq!(conditionals.len(), 2);
+
+    let first = &conditionals[0];
+    assert_eq!(first.branches.len(), 3);
+    assert_eq!(
+        first.branches[0].condition,
+        vec!["option", "vendor-class-identifier", "=", "\"PXEClient\""]
+    );
+    assert_eq!(
+        first.branches[0].scope.parameters["filename"],
+        "\"pxelinux.0\""
+    );
+    assert_eq!(first.branches[1].condition, vec!["exists", "user-class"]);
+    assert_eq!(first.branches[2].condition, vec!["known"]);
+    assert_eq!(first.branches[2].scope.options["routers"], "10.0.0.1");
+    assert_eq!(
+        first.otherwise.as_ref().unwrap().parameters["ignore"],
+        "booting"
+    );
+
+    assert!(conditionals[1].otherwise.is_none());
+    assert_eq!(config.global.default_lease_time(), Some(600));
+
+    assert!(conf::parse("if { }").is_err());
+    assert!(conf::parse("if known { } else").is_err());
+    assert!(conf::parse("if known;").is_err());
+}
+
+#[test]
+fn ddns_test() {
+    let res = conf::parse(
+        "
+    ddns-update-style standard;
+
+    key DHCP_UPDATER {
+        algorithm hmac-md5;
+        secret \"pRP5FapFoJ95JEL06sv4PQ==\";
+    };
+
+    zone example.org. {
+        primary 127.0.0.1;
+        key DHCP_UPDATER;
+    }
+
+    zone \"0.0.10.in-addr.arpa.\" {
+        primary ns1.example.org;
+        secondary ns2.example.org;
+        primary6 ::1;
+    }",
+    );
+
+    let config = res.unwrap();
+    let key = &config.global.keys[0];
+    assert_eq!(key.name, "DHCP_UPDATER");
+    assert_eq!(key.algorithm, Some("hmac-md5".to_owned()));
+    assert_eq!(key.secret, Some("pRP5FapFoJ95JEL06sv4PQ==".to_owned()));
+
+    let zones = &config.global.zones;
+    assert_eq!(zones[0].name, "example.org.");
+    assert_eq!(zones[0].primary, Some("127.0.0.1".to_owned()));
+    assert_eq!(zones[0].key, Some("DHCP_UPDATER".to_owned()));
+    assert_eq!(zones[1].name, "0.0.10.in-addr.arpa.");
+    assert_eq!(zones[1].secondary, Some("ns2.example.org".to_owned()));
+    assert!(zones[1].key.is_none());
+    assert_eq!(zones[1].statements, vec!["primary6 ::1"]);
+
+    assert!(conf::parse("key foo { algorithm hmac-md5; port 53; }").is_err());
+    assert!(conf::parse("zone example.org. { primary 127.0.0.1;").is_err());
+}
+
+#[test]
+fn failover_peer_test() {
+    let res = conf::parse(
+        "
+    failover peer \"dhcp-failover\" {
+        primary;
+        address 10.0.0.1;
+        port 647;
+        peer address 10.0.0.2;
+        peer port 847;
+        max-response-delay 60;
+        max-unacked-updates 10;
+        mclt 3600;
+        split 128;
+        load balance max seconds 3;
+        auto-partner-down 0;
+    }
+
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        pool {
+            failover peer \"dhcp-failover\";
+            range 10.0.0.10 10.0.0.100;
+        }
+    }",
+    );
+
+    let config = res.unwrap();
+    let peer = &config.global.failover_peers[0];
+    assert_eq!(peer.name, "dhcp-failover");
+    assert_eq!(peer.role, Some(FailoverRole::Primary));
+    assert_eq!(peer.address, Some("10.0.0.1".to_owned()));
+    assert_eq!(peer.port, Some(647));
+    assert_eq!(peer.peer_address, Some("10.0.0.2".to_owned()));
+    assert_eq!(peer.peer_port, Some(847));
+    assert_eq!(peer.max_response_delay, Some(60));
+    assert_eq!(peer.max_unacked_updates, Some(10));
+    assert_eq!(peer.mclt, Some(3600));
+    assert_eq!(peer.split, Some(128));
+    assert!(peer.hba.is_none());
+    assert_eq!(peer.load_balance_max_seconds, Some(3));
+    assert_eq!(peer.statements, vec!["auto-partner-down 0"]);
+
+    let pool = &config.global.subnets[0].scope.pools[0];
+    assert_eq!(pool.failover_peer, Some("dhcp-failover".to_owned()));
+
+    assert!(conf::parse("failover peer \"p\" { split 300; }").is_err());
+    assert!(conf::parse("failover peer \"p\" { peer name foo; }").is_err());
+    assert!(conf::parse("failover peer \"p\" { primary secondary; }").is_err());
+}
+
+#[test]
+fn navigation_test() {
+    let config: DhcpdConfig = "
+    failover peer \"dhcp-failover\" {
+        secondary;
+    }
+
+    class \"voip\" {
+        match if option vendor-class-identifier = \"SIP\";
+    }
+
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        host printer {
+            fixed-address 10.0.0.5;
+        }
+    }
+
+    shared-network floor {
+        subnet 10.0.1.0 netmask 255.255.255.0 {
+            pool {
+                range 10.0.1.10 10.0.1.20;
+            }
+        }
+    }
+
+    group {
+        host laptop {
+        }
+        subnet 10.0.2.0 netmask 255.255.255.0 {
+        }
+    }"
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.subnets().len(), 3);
+    assert_eq!(config.shared_networks().len(), 1);
+    assert_eq!(config.pools().len(), 1);
+    assert_eq!(config.hosts().len(), 2);
+    assert_eq!(config.classes().len(), 1);
+    assert_eq!(config.failover_peers().len(), 1);
+
+    assert!(config.host("laptop").is_some());
+    assert!(config.host("phone").is_none());
+    assert_eq!(
+        config.failover_peer("dhcp-failover").unwrap().role,
+        Some(FailoverRole::Secondary)
+    );
+    assert_eq!(
+        config
+            .subnet_for(Ipv4Addr::new(10, 0, 1, 15))
+            .unwrap()
+            .network,
+        Ipv4Addr::new(10, 0, 1, 0)
+    );
+    assert!(config.subnet_for(Ipv4Addr::new(192, 168, 0, 1)).is_none());
+}
+
+#[test]
+fn subnet6_test() {
+    let res = conf::parse(
+        "
+    subnet6 2001:db8:0:1::/64 {
+        range6 2001:db8:0:1::100 2001:db8:0:1::200;
+        range6 2001:db8:0:1:1::/80 temporary;
+        prefix6 2001:db8:100:: 2001:db8:f00:: /56;
+        option dhcp6.name-servers 2001:db8::53;
+
+        pool6 {
+            range6 2001:db8:0:1::/120;
+            allow known-clients;
+        }
+    }",
+    );
+
+    let config = res.unwrap();
+    let subnet = &config.global.subnets6[0];
+    assert_eq!(
+        subnet.network,
+        "2001:db8:0:1::".parse::<Ipv6Addr>().unwrap()
+    );
+    assert_eq!(subnet.prefix_len, 64);
+    assert!(subnet.contains("2001:db8:0:1::42".parse().unwrap()));
+    assert!(!subnet.contains("2001:db8:0:2::42".parse().unwrap()));
+    assert_eq!(subnet.scope.options["dhcp6.name-servers"], "2001:db8::53");
+
+    let ranges = &subnet.scope.ranges6;
+    assert!(ranges[0].contains("2001:db8:0:1::150".parse().unwrap()));
+    assert!(!ranges[0].temporary);
+    assert_eq!(
+        ranges[1].start,
+        "2001:db8:0:1:1::".parse::<Ipv6Addr>().unwrap()
+    );
+    assert_eq!(
+        ranges[1].end,
+        "2001:db8:0:1:1:ffff:ffff:ffff".parse::<Ipv6Addr>().unwrap()
+    );
+    assert!(ranges[1].temporary);
+
+    let prefix = &subnet.scope.prefixes6[0];
+    assert_eq!(prefix.start, "2001:db8:100::".parse::<Ipv6Addr>().unwrap());
+    assert_eq!(prefix.prefix_len, 56);
+
+    let pool = &subnet.scope.pools6[0];
+    assert_eq!(pool.allow, vec!["known-clients"]);
+    assert_eq!(
+        pool.scope.ranges6[0].end,
+        "2001:db8:0:1::ff".parse::<Ipv6Addr>().unwrap()
+    );
+
+    assert!(config
+        .subnet6_for("2001:db8:0:1::1".parse().unwrap())
+        .is_some());
+
+    assert!(conf::parse("subnet6 2001:db8::/129 { }").is_err());
+    assert!(conf::parse("subnet6 2001:db8:: { }").is_err());
+    assert!(conf::parse("range6 2001:db8::/64 permanent;").is_err());
+    assert!(conf::parse("prefix6 2001:db8:100:: 2001:db8:f00:: 56;").is_err());
+}
+
+#[test]
+fn locate_leases_test() {
+    let config = conf::parse(
+        "
+    subnet 10.0.0.0 netmask 255.255.255.0 {
+        range 10.0.0.200 10.0.0.210;
+        pool {
+            range 10.0.0.10 10.0.0.100;
+        }
+    }
+
+    subnet 10.0.1.0 netmask 255.255.255.0 {
+    }",
+    )
+    .unwrap();
+
+    let leases = parser::parse(
+        "
+    lease 10.0.0.42 {
+    }
+    lease 10.0.0.205 {
+    }
+    lease 10.0.0.150 {
+    }
+    lease 10.0.1.5 {
+    }
+    lease 192.168.0.2 {
+    }",
+    )
+    .unwrap()
+    .leases
+    .all();
+
+    let locations = config.locate_leases(&leases);
+    assert_eq!(locations.len(), 5);
+
+    let subnet = &config.global.subnets[0];
+    assert_eq!(locations[0].subnet, Some(subnet));
+    assert_eq!(locations[0].pool, Some(&subnet.scope.pools[0]));
+    assert!(locations[0].in_range);
+
+    assert_eq!(locations[1].subnet, Some(subnet));
+    assert!(locations[1].pool.is_none());
+    assert!(locations[1].in_range);
+
+    assert!(!locations[2].in_range);
+    assert_eq!(locations[3].subnet, Some(&config.global.subnets[1]));
+    assert!(locations[4].subnet.is_none());
+
+    let outside: Vec<String> = config
+        .leases_outside_ranges(&leases)
+        .iter()
+        .map(|l| l.ip.to_string())
+        .collect();
+    assert_eq!(outside, vec!["10.0.0.150", "10.0.1.5", "192.168.0.2"]);
+}
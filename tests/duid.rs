@@ -0,0 +1,75 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::duid::Duid;
+use crate::dhcpd_parser::parser;
+
+#[test]
+fn duid_types_test() {
+    assert_eq!(
+        "0:1:0:1:23:8a:c9:18:0:c:29:b3:ab:1f"
+            .parse::<Duid>()
+            .unwrap(),
+        Duid::Llt {
+            hardware_type: 1,
+            time: 0x238a_c918,
+            link_layer_address: vec![0x00, 0x0c, 0x29, 0xb3, 0xab, 0x1f],
+        }
+    );
+    assert_eq!(
+        "\"\\000\\002\\000\\000\\000\\0117abc\""
+            .parse::<Duid>()
+            .unwrap(),
+        Duid::En {
+            enterprise_number: 9,
+            identifier: b"7abc".to_vec(),
+        }
+    );
+    assert_eq!(
+        Duid::from_bytes(&[0, 3, 0, 1, 0xaa, 0xbb]).unwrap(),
+        Duid::Ll {
+            hardware_type: 1,
+            link_layer_address: vec![0xaa, 0xbb],
+        }
+    );
+    assert_eq!(
+        Duid::from_bytes(&[0, 4, 1, 2]).unwrap(),
+        Duid::Other {
+            duid_type: 4,
+            data: vec![1, 2],
+        }
+    );
+    assert!(Duid::from_bytes(&[0, 1, 0, 1]).is_err());
+    assert!("Client1".parse::<Duid>().is_err());
+}
+
+#[test]
+fn duid_from_leases_test() {
+    let res = parser::parse(
+        "
+    server-duid \"\\000\\003\\000\\001\\000\\014)\\263\\253\\037\";
+
+    ia-na \"\\001\\000\\000\\000\\000\\003\\000\\001\\000\\014)\\001\\002\\003\" {
+        cltt 1 2019/01/07 10:00:00;
+    }
+
+    lease 192.168.0.2 {
+        uid ff:0:0:0:1:0:3:0:1:0:c:29:1:2:3;
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.server_duid.unwrap().parse::<Duid>().unwrap(),
+        Duid::Ll {
+            hardware_type: 1,
+            link_layer_address: vec![0x00, 0x0c, 0x29, 0xb3, 0xab, 0x1f],
+        }
+    );
+
+    let client = Duid::Ll {
+        hardware_type: 1,
+        link_layer_address: vec![0x00, 0x0c, 0x29, 0x01, 0x02, 0x03],
+    };
+    assert_eq!(res.ias[0].duid().unwrap(), client);
+    assert_eq!(res.leases[0].client_duid().unwrap(), client);
+}
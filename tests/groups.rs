@@ -0,0 +1,44 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::parser;
+
+#[test]
+fn dynamic_group_test() {
+    let res = parser::parse(
+        "
+    group \"printers\" {
+        dynamic;
+        option domain-name-servers 10.0.0.1;
+        if exists agent.circuit-id {
+            set relayed = true;
+        }
+    }
+
+    subgroup \"lab\" {
+        dynamic;
+    }
+
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(res.groups.len(), 2);
+
+    let group = &res.groups[0];
+    assert_eq!(group.name, "printers");
+    assert!(group.dynamic);
+    assert!(!group.subgroup);
+    assert_eq!(
+        group.statements,
+        vec![
+            "option domain-name-servers 10.0.0.1",
+            "if exists agent.circuit-id { set relayed = true; }",
+        ]
+    );
+
+    assert_eq!(res.groups[1].name, "lab");
+    assert!(res.groups[1].subgroup);
+    assert!(res.groups[1].statements.is_empty());
+}
@@ -0,0 +1,82 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::parser::LeasesMethods;
+
+#[test]
+fn dynamic_host_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+    }
+
+    host printer {
+        dynamic;
+        hardware ethernet 00:11:22:33:44:55;
+        fixed-address 192.168.0.10;
+        supersede host-name = \"printer\";
+    }
+
+    host \"laptop\" {
+        dynamic;
+        uid \"\\001\\000\\021\\042\\063\\104\\125\";
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(res.leases.all().len(), 1);
+    assert_eq!(res.hosts.len(), 2);
+
+    let host = &res.hosts[0];
+    assert_eq!(host.name, "printer");
+    assert!(host.dynamic);
+    assert_eq!(
+        host.hardware.as_ref().unwrap().mac.as_ref().unwrap(),
+        "00:11:22:33:44:55"
+    );
+    assert_eq!(host.fixed_address.as_ref().unwrap(), "192.168.0.10");
+    assert_eq!(host.statements, vec!["supersede host-name = \"printer\""]);
+
+    let host = &res.hosts[1];
+    assert_eq!(host.name, "laptop");
+    assert!(host.hardware.is_none());
+    assert!(host.uid.is_some());
+}
+
+#[test]
+fn unterminated_host_test() {
+    let res = parser::parse(
+        "
+    host printer {
+        dynamic;
+    ",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn deleted_test() {
+    let res = parser::parse(
+        "
+    host printer {
+        dynamic;
+        hardware ethernet 00:11:22:33:44:55;
+    }
+
+    host printer {
+        dynamic;
+        deleted;
+    }
+
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        deleted;
+    }",
+    )
+    .unwrap();
+
+    assert!(!res.hosts[0].deleted);
+    assert!(res.hosts[1].deleted);
+    assert!(res.leases[0].deleted);
+}
@@ -0,0 +1,207 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::ia::IaKind;
+use crate::dhcpd_parser::leases::BindingState;
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::parser::ParseMode;
+use crate::dhcpd_parser::parser::ParserOptions;
+use crate::dhcpd_parser::parser::Severity;
+
+#[test]
+fn ia_na_test() {
+    let res = parser::parse(
+        "
+    server-duid \"\\000\\001\\000\\001#\\212\\311\\030\\000\\014)\\263\\253\\037\";
+
+    ia-na \"\\001\\000\\000\\000\\000\\001\\000\\001\\030\\214\" {
+        cltt 1 2019/01/07 10:00:00;
+        iaaddr 2001:db8::1234 {
+            binding state active;
+            preferred-life 375;
+            max-life 600;
+            ends 1 2019/01/07 10:10:00;
+        }
+        iaaddr 2001:db8::1235 {
+            binding state expired;
+        }
+        iaaddr 2001:db8::1236 {
+            binding state active;
+            ends never;
+        }
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(res.ias.len(), 1);
+
+    let ia = &res.ias[0];
+    assert_eq!(ia.kind, IaKind::Na);
+    assert_eq!(ia.id, "\\001\\000\\000\\000\\000\\001\\000\\001\\030\\214");
+    assert_eq!(ia.cltt.unwrap().to_string(), "2019-01-07T10:00:00Z");
+    assert_eq!(ia.addresses.len(), 3);
+
+    let address = &ia.addresses[0];
+    assert_eq!(address.address, "2001:db8::1234");
+    assert_eq!(
+        address.binding_state.as_ref().unwrap(),
+        &BindingState::Active
+    );
+    assert_eq!(address.preferred_life, Some(375));
+    assert_eq!(address.max_life, Some(600));
+    assert_eq!(address.ends.unwrap().to_string(), "2019-01-07T10:10:00Z");
+    assert!(!address.never_ends);
+
+    let address = &ia.addresses[1];
+    assert_eq!(address.address, "2001:db8::1235");
+    assert_eq!(
+        address.binding_state.as_ref().unwrap(),
+        &BindingState::Expired
+    );
+    assert!(address.ends.is_none());
+    assert!(!address.never_ends);
+
+    let address = &ia.addresses[2];
+    assert!(address.ends.is_none());
+    assert!(address.never_ends);
+}
+
+#[test]
+fn invalid_ia_test() {
+    let res = parser::parse(
+        "
+    ia-na \"\\001\\000\\000\\000\" {
+        iaaddr 2001:db8::1234;
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn ia_ta_test() {
+    let res = parser::parse(
+        "
+    ia-ta \"\\002\\000\\000\\000\\000\\001\\000\\001\\030\\214\" {
+        cltt 1 2019/01/07 10:00:00;
+        iaaddr fe80::1:2 {
+            binding state active;
+            preferred-life 375;
+            max-life 600;
+            ends 1 2019/01/07 10:10:00;
+        }
+    }
+
+    ia-na \"\\001\\000\\000\\000\\000\\001\\000\\001\\030\\214\" {
+        cltt 1 2019/01/07 10:00:00;
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(res.ias.len(), 2);
+    assert_eq!(res.ias[0].kind, IaKind::Ta);
+    assert_eq!(res.ias[0].addresses[0].address, "fe80::1:2");
+    assert_eq!(res.ias[1].kind, IaKind::Na);
+    assert!(res.ias[1].addresses.is_empty());
+}
+
+#[test]
+fn ia_pd_test() {
+    let res = parser::parse(
+        "
+    ia-pd \"\\003\\000\\000\\000\\000\\001\\000\\001\\030\\214\" {
+        cltt 1 2019/01/07 10:00:00;
+        iaprefix 2001:db8:1::/56 {
+            binding state active;
+            preferred-life 375;
+            max-life 600;
+            ends 1 2019/01/07 10:10:00;
+        }
+        iaprefix 2001:db8:2::/56 {
+            binding state free;
+        }
+        iaprefix 2001:db8:3::/56 {
+            ends never;
+        }
+    }",
+    )
+    .unwrap();
+
+    let ia = &res.ias[0];
+    assert_eq!(ia.kind, IaKind::Pd);
+    assert_eq!(ia.prefixes.len(), 3);
+
+    let prefix = &ia.prefixes[0];
+    assert_eq!(prefix.prefix, "2001:db8:1::/56");
+    assert_eq!(
+        prefix.binding_state.as_ref().unwrap(),
+        &BindingState::Active
+    );
+    assert_eq!(prefix.preferred_life, Some(375));
+    assert_eq!(prefix.max_life, Some(600));
+    assert_eq!(prefix.ends.unwrap().to_string(), "2019-01-07T10:10:00Z");
+
+    let prefix = &ia.prefixes[1];
+    assert_eq!(prefix.binding_state.as_ref().unwrap(), &BindingState::Free);
+    assert!(prefix.max_life.is_none());
+    assert!(prefix.ends.is_none());
+    assert!(!prefix.never_ends);
+
+    let prefix = &ia.prefixes[2];
+    assert!(prefix.ends.is_none());
+    assert!(prefix.never_ends);
+
+    let res = parser::parse(
+        "
+    ia-pd \"\\003\\000\\000\\000\" {
+        iaprefix 2001:db8:1::/56 {
+            max-life forever;
+        }
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn ia_lenient_test() {
+    let input = "
+    ia-na \"\\001\\000\\000\\000\" {
+        cltt 1 2019/01/07 10:00:00;
+        future-statement 42;
+        iaaddr 2001:db8::1 {
+            binding state active;
+            future-option \"x\" { y; }
+            ends 1 2019/01/07 10:10:00;
+        }
+    }
+    ia-pd \"\\003\\000\\000\\000\" {
+        iaprefix 2001:db8:1::/56 {
+            hardware ethernet 00:11:22:33:44:55;
+        }
+    }";
+
+    assert!(parser::parse(input).is_err());
+
+    let options = ParserOptions {
+        mode: ParseMode::Lenient,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(res.ias.len(), 2);
+    let address = &res.ias[0].addresses[0];
+    assert_eq!(address.binding_state, Some(BindingState::Active));
+    assert!(address.ends.is_some());
+    assert_eq!(res.ias[1].prefixes.len(), 1);
+
+    let messages: Vec<&str> = res.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Skipped unknown statement 'future-statement 42' in ia-na",
+            "Skipped unknown statement 'future-option \"x\" { y; }' in iaaddr 2001:db8::1",
+            "Skipped unknown statement 'hardware ethernet 00:11:22:33:44:55' in iaprefix 2001:db8:1::/56",
+        ]
+    );
+    assert!(res
+        .diagnostics
+        .iter()
+        .all(|d| d.severity == Severity::Warning));
+}
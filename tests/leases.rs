@@ -1,8 +1,28 @@
 extern crate dhcpd_parser;
 
+use std::convert::TryFrom;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+
 use crate::dhcpd_parser::common::Date;
+use crate::dhcpd_parser::common::MacAddr;
+use crate::dhcpd_parser::conf::Range;
+use crate::dhcpd_parser::leases::BindingState;
+use crate::dhcpd_parser::leases::Hardware;
+use crate::dhcpd_parser::leases::HardwareType;
+use crate::dhcpd_parser::leases::Lease;
+use crate::dhcpd_parser::leases::Leases;
+use crate::dhcpd_parser::leases::LeasesField;
+use crate::dhcpd_parser::leases::MergeDuplicates;
+use crate::dhcpd_parser::leases::SortOrder;
 use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::parser::DuplicatePolicy;
 use crate::dhcpd_parser::parser::LeasesMethods;
+use crate::dhcpd_parser::parser::Limits;
+use crate::dhcpd_parser::parser::ParseError;
+use crate::dhcpd_parser::parser::ParseMode;
+use crate::dhcpd_parser::parser::ParserOptions;
+use crate::dhcpd_parser::parser::Severity;
 
 #[test]
 fn basic_test() {
@@ -75,7 +95,7 @@ fn multiple_leases_test() {
     assert_eq!(leases[0].hostname.as_ref().unwrap(), "TESTHOSTNAME");
     assert_eq!(
         leases[1].dates.starts.unwrap().to_string(),
-        "Monday 1985/01/01 00:00:00"
+        "1985-01-01T00:00:00Z"
     );
     assert!(leases[1].dates.ends.is_none());
 
@@ -106,6 +126,7 @@ fn invalid_date_format_test() {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn is_active_test() {
     let res = parser::parse(
         "
@@ -229,3 +250,2533 @@ fn client_hostnames_test() {
             .collect()
     );
 }
+
+#[test]
+fn macs_ips_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hardware ethernet 11:11:11:11:11:11;
+    }
+    lease 192.168.0.3 {
+        hardware ethernet 22:22:22:22:22:22;
+    }
+    lease 192.168.0.3 {
+        hardware ethernet 22:22:22:22:22:22;
+    }
+    lease 192.168.0.4 {
+    }
+    ",
+    );
+
+    let leases = res.unwrap().leases;
+
+    assert_eq!(
+        leases.macs(),
+        ["11:11:11:11:11:11", "22:22:22:22:22:22"]
+            .iter()
+            .map(|m| m.parse::<MacAddr>().unwrap())
+            .collect()
+    );
+    assert_eq!(
+        leases.ips(),
+        ["192.168.0.2", "192.168.0.3", "192.168.0.4"]
+            .iter()
+            .map(|ip| ip.parse::<IpAddr>().unwrap())
+            .collect()
+    );
+}
+
+#[test]
+fn failover_dates_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+        tstp 2 2019/01/01 23:30:00;
+        tsfp 2 2019/01/01 23:15:00;
+        atsfp 2 2019/01/01 23:15:00;
+        hardware ethernet 11:11:11:11:11:11;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases[0].tstp.unwrap().to_string(), "2019-01-01T23:30:00Z");
+    assert_eq!(leases[0].tsfp.unwrap().to_string(), "2019-01-01T23:15:00Z");
+    assert_eq!(leases[0].atsfp, leases[0].tsfp);
+}
+
+#[test]
+fn relay_agent_options_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        hardware ethernet 11:11:11:11:11:11;
+        option agent.circuit-id \"eth0/1/2\";
+        option agent.remote-id 0:1b:21:3c:4d:5e;
+    }
+
+    lease 192.168.0.3 {
+        option agent.circuit-id 0:4:0:1:0:2;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases[0].agent_circuit_id.as_deref().unwrap(), b"eth0/1/2");
+    assert_eq!(
+        leases[1].agent_circuit_id.as_deref().unwrap(),
+        [0, 4, 0, 1, 0, 2]
+    );
+    assert_eq!(
+        leases[0].agent_remote_id.as_deref().unwrap(),
+        [0x00, 0x1b, 0x21, 0x3c, 0x4d, 0x5e]
+    );
+    assert!(leases[1].agent_remote_id.is_none());
+
+    // identifiers are binary, bytes that are not UTF-8 are kept as is
+    let res = parser::parse(
+        "
+    lease 192.168.0.4 {
+        option agent.remote-id \"\\000\\006\\377\\200ab\";
+    }",
+    );
+    assert_eq!(
+        res.unwrap().leases[0].agent_remote_id.as_deref().unwrap(),
+        [0, 6, 0xff, 0x80, b'a', b'b']
+    );
+}
+
+#[test]
+fn set_variables_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        set vendor-class-identifier = \"MSFT 5.0\";
+        set ddns-fwd-name = \"host.example.com\";
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases[0].variables.len(), 2);
+    assert_eq!(
+        leases[0].variables["vendor-class-identifier"],
+        "\"MSFT 5.0\""
+    );
+    assert_eq!(leases[0].variables["ddns-fwd-name"], "\"host.example.com\"");
+    assert_eq!(
+        leases[0].vendor_class_identifier(),
+        Some("MSFT 5.0".to_owned())
+    );
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        set vendor-class-identifier \"MSFT 5.0\";
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn ddns_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        set ddns-rev-name = \"2.0.168.192.in-addr.arpa.\";
+        set ddns-txt = \"31a9f4b3a7e7d7e1a1c1b1d1e1f1a1b1c1\";
+        set ddns-fwd-name = \"host.example.com\";
+    }
+
+    lease 192.168.0.3 {
+        set vendor-class-identifier = \"MSFT 5.0\";
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    let ddns = leases[0].ddns.as_ref().unwrap();
+    assert_eq!(ddns.fwd_name.as_ref().unwrap(), "host.example.com");
+    assert_eq!(ddns.rev_name.as_ref().unwrap(), "2.0.168.192.in-addr.arpa.");
+    assert_eq!(
+        ddns.txt.as_ref().unwrap(),
+        "31a9f4b3a7e7d7e1a1c1b1d1e1f1a1b1c1"
+    );
+    assert!(ddns.dhcid.is_none());
+    assert!(leases[1].ddns.is_none());
+}
+
+#[test]
+fn flags_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        reserved;
+    }
+
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+        bootp;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert!(leases[0].reserved);
+    assert!(!leases[1].reserved);
+    assert!(!leases[0].bootp);
+    assert!(leases[1].bootp);
+}
+
+#[test]
+fn billing_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        billing class \"limited\";
+    }
+
+    lease 192.168.0.3 {
+        billing subclass \"vendor\" \"MSFT\";
+    }
+
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 22:00:00;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    let billing = leases[0].billing.as_ref().unwrap();
+    assert_eq!(billing.class, "limited");
+    assert!(billing.subclass.is_none());
+
+    let billing = leases[1].billing.as_ref().unwrap();
+    assert_eq!(billing.class, "vendor");
+    assert_eq!(billing.subclass.as_ref().unwrap(), "MSFT");
+
+    assert!(leases[2].billing.is_none());
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        billing group \"limited\";
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn on_events_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        on expiry or release {
+            set ddns-fwd-name = \"host.example.com\";
+            if exists ddns-fwd-name {
+                unset ddns-fwd-name;
+            }
+            on-expiry-done;
+        }
+        on commit {
+            execute \"/usr/local/bin/notify\";
+        }
+        hostname \"TESTHOSTNAME\";
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases[0].hostname.as_ref().unwrap(), "TESTHOSTNAME");
+    assert_eq!(leases[0].on_events.len(), 2);
+
+    let event = &leases[0].on_events[0];
+    assert_eq!(event.events, vec!["expiry", "release"]);
+    assert_eq!(
+        event.statements,
+        vec![
+            "set ddns-fwd-name = \"host.example.com\"",
+            "if exists ddns-fwd-name { unset ddns-fwd-name; }",
+            "on-expiry-done",
+        ]
+    );
+    assert_eq!(leases[0].on_events[1].events, vec!["commit"]);
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        on expiry {
+            set ddns-fwd-name = \"host.example.com\";
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn server_duid_test() {
+    let res = parser::parse(
+        "
+    server-duid \"\\000\\001\\000\\001#\\212\\311\\030\\000\\014)\\263\\253\\037\";
+
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.server_duid.unwrap(),
+        "\"\\000\\001\\000\\001#\\212\\311\\030\\000\\014)\\263\\253\\037\""
+    );
+    assert_eq!(res.leases[0].ip.to_string(), "192.168.0.2");
+
+    let res = parser::parse("lease 192.168.0.2 { }").unwrap();
+    assert!(res.server_duid.is_none());
+}
+
+#[test]
+fn authoring_byte_order_test() {
+    let res = parser::parse(
+        "
+    authoring-byte-order little-endian;
+
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+    }",
+    )
+    .unwrap();
+
+    assert_eq!(res.authoring_byte_order.unwrap(), "little-endian");
+    assert_eq!(res.leases[0].ip.to_string(), "192.168.0.2");
+
+    let res = parser::parse("authoring-byte-order;");
+    assert!(res.is_err());
+}
+
+#[test]
+fn uid_bytes_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        uid \"\\001\\000\\014)\\316\\230\\020\";
+    }
+
+    lease 192.168.0.3 {
+        uid 1:0:c:29:CE:98:10;
+    }
+
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 22:00:00;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(
+        leases[0].uid_bytes().unwrap(),
+        vec![0x01, 0x00, 0x0c, 0x29, 0xce, 0x98, 0x10]
+    );
+    assert_eq!(leases[0].uid_bytes(), leases[1].uid_bytes());
+    assert_eq!(leases[0].normalized_uid().unwrap(), "01:00:0c:29:ce:98:10");
+    assert_eq!(leases[0].normalized_uid(), leases[1].normalized_uid());
+    assert_eq!(leases[0].uid, leases[1].uid);
+    assert_eq!(
+        leases[1].uid.as_ref().unwrap().as_str(),
+        "1:0:c:29:CE:98:10"
+    );
+    assert_eq!(leases[0].uid.as_ref().unwrap(), "01:00:0c:29:ce:98:10");
+    assert!(leases[2].uid_bytes().is_none());
+}
+
+#[test]
+fn hardware_type_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hardware ethernet 11:11:11:11:11:11;
+    }
+
+    lease 192.168.0.3 {
+        hardware token-ring 22:22:22:22:22:22;
+    }
+
+    lease 192.168.0.4 {
+        hardware type 33:33:33:33:33:33;
+    }
+
+    lease 192.168.0.5 {
+        hardware ethernet;
+        uid 1:2:3;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    let h_type = |i: usize| leases[i].hardware.as_ref().unwrap().h_type.clone();
+    assert_eq!(h_type(0), HardwareType::Ethernet);
+    assert_eq!(h_type(1), HardwareType::TokenRing);
+    assert_eq!(h_type(2), HardwareType::Other("type".to_owned()));
+    assert_eq!(h_type(1).to_string(), "token-ring");
+    assert_eq!("fddi".parse::<HardwareType>().unwrap(), HardwareType::Fddi);
+    assert_eq!(
+        "infiniband".parse::<HardwareType>().unwrap(),
+        HardwareType::Infiniband
+    );
+
+    assert_eq!(
+        leases[0].hardware.as_ref().unwrap().mac.as_ref().unwrap(),
+        "11:11:11:11:11:11"
+    );
+    assert_eq!(h_type(3), HardwareType::Ethernet);
+    assert!(leases[3].hardware.as_ref().unwrap().mac.is_none());
+    assert_eq!(leases[3].uid.as_ref().unwrap(), "1:2:3");
+}
+
+#[test]
+fn binding_states_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+        next binding state free;
+        rewind binding state backup;
+        hardware ethernet 11:11:11:11:11:11;
+    }
+
+    lease 192.168.0.3 {
+        binding state bootp;
+    }
+
+    lease 192.168.0.4 {
+        binding state active dynamic-bootp;
+        next binding state free;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases[0].binding_state, Some(BindingState::Active));
+    assert_eq!(leases[0].next_binding_state, Some(BindingState::Free));
+    assert_eq!(leases[0].rewind_binding_state, Some(BindingState::Backup));
+    assert_eq!(
+        leases[1].binding_state,
+        Some(BindingState::Other("bootp".to_owned()))
+    );
+    assert!(leases[1].next_binding_state.is_none());
+    assert!(leases[0].binding_flags.is_empty());
+    assert_eq!(leases[2].binding_state, Some(BindingState::Active));
+    assert_eq!(leases[2].binding_flags, vec!["dynamic-bootp"]);
+
+    assert_eq!(
+        BindingState::try_from("released").unwrap(),
+        BindingState::Released
+    );
+    assert_eq!(BindingState::Abandoned.to_string(), "abandoned");
+    assert!(BindingState::try_from("").is_err());
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        next binding active;
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn duplicate_statements_test() {
+    let input = "
+    lease 192.168.0.2 {
+        client-hostname \"first\";
+        abandoned;
+        client-hostname \"second\";
+        abandoned;
+    }";
+
+    let res = parser::parse(input).unwrap();
+    assert_eq!(res.leases[0].client_hostname, Some("second".to_owned()));
+    assert_eq!(res.diagnostics.len(), 1);
+    assert!(res.leases[0].duplicates.is_empty());
+
+    let options = ParserOptions {
+        duplicates: DuplicatePolicy::FirstWins,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(res.leases[0].client_hostname, Some("first".to_owned()));
+    assert_eq!(res.diagnostics.len(), 1);
+
+    let options = ParserOptions {
+        duplicates: DuplicatePolicy::CollectAll,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(res.leases[0].client_hostname, Some("second".to_owned()));
+    assert_eq!(
+        res.leases[0].duplicates["client-hostname"],
+        vec!["client-hostname \"first\"", "client-hostname \"second\""]
+    );
+}
+
+#[test]
+fn minimal_lease_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.5 { }
+    lease 10.0.0.6 {
+        ends 4 2019/01/01 22:00:00;
+    }
+    lease 10.0.0.7 {
+        binding state free;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases.all().len(), 3);
+    assert_eq!(leases[0].ip.to_string(), "10.0.0.5");
+    assert!(leases[0].dates.starts.is_none());
+    assert!(leases[0].dates.ends.is_none());
+    assert!(leases[0].hardware.is_none());
+    assert!(leases[0].is_active_at(Date::from("1", "2019/01/01", "22:00:00").unwrap()));
+    assert!(leases[1].dates.starts.is_none());
+    assert!(!leases[1].is_active_at(Date::from("1", "2019/01/02", "22:00:00").unwrap()));
+    assert_eq!(leases[2].binding_state, Some(BindingState::Free));
+
+    assert!(leases.hostnames().is_empty());
+
+    assert!(parser::parse("lease 10.0.0.5 {").is_err());
+    assert!(parser::parse("lease 10.0.0.5").is_err());
+    assert!(parser::parse("lease").is_err());
+}
+
+#[test]
+fn ends_never_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00 UTC;
+        ends never;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert!(leases[0].dates.never_ends);
+    assert!(leases[0].dates.ends.is_none());
+    assert!(leases[0].is_active_at(Date::from("1", "2100/01/01", "00:00:00").unwrap()));
+    assert!(!leases[0].is_active_at(Date::from("1", "2018/01/01", "00:00:00").unwrap()));
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        ends never 2019/01/01;
+    }",
+    );
+    assert!(res.is_err());
+    // dhcpd only writes never for end dates
+    for statement in &["starts never;", "tstp never;", "atsfp never;"] {
+        let input = format!("lease 192.168.0.2 {{ {} }}", statement);
+        let err = parser::parse(input).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDate { .. }));
+    }
+}
+
+#[test]
+fn epoch_dates_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts epoch 1687190400; # Mon Jun 19 16:00:00 2023
+        ends epoch 1687194000; # Mon Jun 19 17:00:00 2023
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(
+        leases[0].dates.starts,
+        Some(Date::from("1", "2023/06/19", "16:00:00").unwrap())
+    );
+    assert_eq!(leases[0].dates.starts.unwrap().weekday, 1);
+    assert_eq!(
+        leases[0].dates.ends,
+        Some(Date::from("1", "2023/06/19", "17:00:00").unwrap())
+    );
+    assert_eq!(
+        leases[0].dates.ends.unwrap().to_unix_timestamp(),
+        Some(1687194000)
+    );
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts epoch yesterday;
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn trailing_comments_test() {
+    let res = parser::parse(
+        "# leases written in local time\r
+    lease 192.168.0.2 {\r
+        starts 1 2023/06/19 18:00:00 # CEST\r
+        ;\r
+        ends 1 2023/06/19 19:00:00;# Mon Jun 19 19:00:00 2023 CEST\r
+        client-hostname \"host#1\"; # comment\r
+    } # end of lease",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(
+        leases[0].dates.starts,
+        Some(Date::from("1", "2023/06/19", "18:00:00").unwrap())
+    );
+    assert_eq!(
+        leases[0].dates.ends,
+        Some(Date::from("1", "2023/06/19", "19:00:00").unwrap())
+    );
+    assert_eq!(leases[0].client_hostname, Some("host#1".to_owned()));
+}
+
+#[test]
+fn comments_test() {
+    let input = "
+    # leases of the first pool
+    lease 192.168.0.2 { # assigned manually
+        starts 2 2019/01/01 22:00:00 UTC;
+        # cable replaced on 2019/01/02
+        ends 2 2019/01/01 23:00:00 # UTC
+        ;
+        on expiry {
+            # not kept
+            set expired = true;
+        }
+        # last comment
+    } # after the lease";
+
+    let res = parser::parse(input).unwrap();
+    assert!(res.leases[0].comments.is_empty());
+
+    let options = ParserOptions {
+        preserve_comments: true,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(
+        res.leases[0].comments,
+        vec![
+            "assigned manually",
+            "cable replaced on 2019/01/02",
+            "last comment"
+        ]
+    );
+    assert_eq!(res.leases[0].on_events.len(), 1);
+}
+
+#[test]
+fn file_info_test() {
+    let res = parser::parse(
+        "# The format of this file is documented in the dhcpd.leases(5) manual page.
+# This lease file was written by isc-dhcp-4.4.1
+# written at 3 2023/06/21 10:15:00
+
+authoring-byte-order little-endian;
+
+# not part of the header
+lease 192.168.0.2 {
+}",
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.file_info.server_version,
+        Some("isc-dhcp-4.4.1".to_owned())
+    );
+    assert_eq!(
+        res.file_info.written,
+        Some(Date::from("3", "2023/06/21", "10:15:00").unwrap())
+    );
+    assert_eq!(res.file_info.comments.len(), 3);
+
+    let res = parser::parse("lease 192.168.0.2 {\n}").unwrap();
+    assert!(res.file_info.server_version.is_none());
+    assert!(res.file_info.written.is_none());
+    assert!(res.file_info.comments.is_empty());
+}
+
+#[test]
+fn generic_options_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        option agent.circuit-id \"eth0/1/2\";
+        option vendor-class-identifier \"MSFT 5.0\";
+        option dhcp-parameter-request-list 1,3,6,15;
+        option fqdn.hostname \"laptop\";
+    }",
+    );
+
+    let lease = &res.unwrap().leases[0];
+    assert_eq!(lease.options.len(), 4);
+    assert_eq!(lease.options["agent.circuit-id"], "\"eth0/1/2\"");
+    assert_eq!(lease.options["vendor-class-identifier"], "\"MSFT 5.0\"");
+    assert_eq!(lease.options["dhcp-parameter-request-list"], "1,3,6,15");
+    assert_eq!(lease.options["fqdn.hostname"], "\"laptop\"");
+    assert_eq!(lease.agent_circuit_id.as_deref().unwrap(), b"eth0/1/2");
+}
+
+#[test]
+fn passthrough_unknown_test() {
+    let input = "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        cltt 2 2019/01/01 22:00:00;
+        new-statement foo { bar; baz; }
+        hardware ethernet 11:11:11:11:11:11;
+    }";
+
+    assert!(parser::parse(input).is_err());
+
+    let options = ParserOptions {
+        passthrough_unknown: true,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(
+        res.leases[0].unknown_statements,
+        vec![
+            "cltt 2 2019/01/01 22:00:00",
+            "new-statement foo { bar; baz; }"
+        ]
+    );
+    assert!(res.leases[0].hardware.is_some());
+}
+
+#[test]
+fn quoted_strings_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        client-hostname \"John's iPad \\\"work\\\"\";
+        option agent.circuit-id \"port 1; vlan 2 # uplink\";
+        set vendor-class-identifier = \"MSFT 5.0\";
+        billing class \"vendor \\\\ class\";
+    }",
+    );
+
+    let lease = &res.unwrap().leases[0];
+    assert_eq!(
+        lease.client_hostname.as_ref().unwrap(),
+        "John's iPad \"work\""
+    );
+    assert_eq!(
+        lease.agent_circuit_id.as_deref().unwrap(),
+        b"port 1; vlan 2 # uplink"
+    );
+    assert_eq!(lease.vendor_class_identifier().unwrap(), "MSFT 5.0");
+    assert_eq!(lease.billing.as_ref().unwrap().class, "vendor \\ class");
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        client-hostname \"unterminated;
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn lease_address_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+    }
+    lease 2001:db8::2 {
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases[0].ip, IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2)));
+    assert!(leases[1].ip.is_ipv6());
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.256 {
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+#[allow(deprecated)]
+fn mac_lookup_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        hardware ethernet 0:1b:c:FF:3:4;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert_eq!(
+        leases[0]
+            .hardware
+            .as_ref()
+            .unwrap()
+            .mac
+            .as_ref()
+            .unwrap()
+            .to_string(),
+        "00:1b:0c:ff:03:04"
+    );
+    assert!(leases.by_mac("00:1B:0C:FF:03:04").is_some());
+    assert_eq!(leases.by_mac_all("0:1b:c:ff:3:4").len(), 1);
+    assert!(leases.by_mac("00:1b:0c:ff:03:05").is_none());
+    assert!(leases
+        .active_by(
+            LeasesField::MAC,
+            "00:1B:0C:FF:03:04",
+            Date::from("2", "2019/01/01", "22:00:00").unwrap()
+        )
+        .is_some());
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hardware ethernet 11:22:gg;
+    }",
+    );
+    assert!(res.is_err());
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_active_at_test() {
+    use chrono::TimeZone;
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    assert!(leases[0].is_active_at(chrono::Utc.with_ymd_and_hms(2019, 1, 1, 22, 30, 0).unwrap()));
+    assert!(!leases[0].is_active_at(chrono::Utc.with_ymd_and_hms(2019, 1, 2, 0, 0, 0).unwrap()));
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_active_at_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    let at = |ts| time::OffsetDateTime::from_unix_timestamp(ts).unwrap();
+    assert!(leases[0].is_active_at(at(1546381800)));
+    assert!(!leases[0].is_active_at(at(1546390800)));
+}
+
+#[test]
+fn lease_state_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+        ends 3 2019/01/02 22:00:00;
+        binding state expired;
+    }
+    lease 192.168.0.4 {
+        binding state free;
+    }
+    lease 192.168.0.5 {
+        ends never;
+        binding state released;
+    }
+    lease 192.168.0.6 {
+        ends 2 2019/01/01 23:00:00;
+        binding state free;
+    }
+    lease 192.168.0.7 {
+        ends 2 2019/01/01 23:00:00;
+    }",
+    );
+
+    let leases = res.unwrap().leases;
+    let at = |s: &str| s.parse::<Date>().unwrap();
+
+    // the binding state takes precedence over the end date
+    assert!(!leases[0].is_expired_at(at("2019-01-01T22:30:00Z")));
+    assert!(!leases[0].is_expired_at(at("2019-01-01T23:00:01Z")));
+    assert!(leases[1].is_expired_at(at("2019-01-01T22:30:00Z")));
+    assert!(!leases[2].is_expired_at(at("2019-01-01T22:30:00Z")));
+    assert!(!leases[3].is_expired_at(at("2100-01-01T00:00:00Z")));
+    assert!(!leases[4].is_expired_at(at("2019-01-01T23:00:01Z")));
+    // without a binding state, the end date is used
+    assert!(!leases[5].is_expired_at(at("2019-01-01T22:30:00Z")));
+    assert!(leases[5].is_expired_at(at("2019-01-01T23:00:01Z")));
+
+    assert!(leases[2].is_free());
+    assert!(!leases[0].is_free());
+    assert!(leases[3].is_released());
+    assert!(!leases[2].is_released());
+}
+
+#[test]
+fn hardware_display_test() {
+    let hw = "ethernet 0:1B:c:ff:3:4".parse::<Hardware>().unwrap();
+    assert_eq!(hw.h_type, HardwareType::Ethernet);
+    assert_eq!(hw.to_string(), "ethernet 00:1b:0c:ff:03:04");
+    assert_eq!(hw.to_string().parse::<Hardware>().unwrap(), hw);
+
+    let hw = "token-ring".parse::<Hardware>().unwrap();
+    assert!(hw.mac.is_none());
+    assert_eq!(hw.to_string(), "token-ring");
+
+    assert!("".parse::<Hardware>().is_err());
+    assert!("ethernet zz:zz".parse::<Hardware>().is_err());
+    assert!("ethernet 00:11 22:33".parse::<Hardware>().is_err());
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hardware ethernet 11:22:33:44:55:66;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    assert_eq!(
+        format!("hardware {};", leases[0].hardware.as_ref().unwrap()),
+        "hardware ethernet 11:22:33:44:55:66;"
+    );
+}
+
+#[test]
+fn lease_builder_test() {
+    let date = |s: &str| s.parse::<Date>().unwrap();
+    let lease = Lease::builder()
+        .ip(Ipv4Addr::new(192, 168, 0, 2))
+        .starts(date("2024-05-01T12:00:00Z"))
+        .ends(date("2024-05-01T13:00:00Z"))
+        .binding_state(BindingState::Active)
+        .hardware("ethernet 00:11:22:33:44:55".parse().unwrap())
+        .uid("01:00:11:22:33:44:55".parse().unwrap())
+        .hostname("laptop")
+        .option("agent.circuit-id", "\"eth0\"")
+        .build()
+        .unwrap();
+
+    assert_eq!(lease.ip, IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2)));
+    assert!(lease.is_active_at(date("2024-05-01T12:30:00Z")));
+    assert_eq!(lease.binding_state, Some(BindingState::Active));
+    assert_eq!(
+        lease.hardware.unwrap().to_string(),
+        "ethernet 00:11:22:33:44:55"
+    );
+    assert_eq!(lease.uid.unwrap(), "1:0:11:22:33:44:55");
+    assert_eq!(lease.hostname.unwrap(), "laptop");
+    assert_eq!(lease.options["agent.circuit-id"], "\"eth0\"");
+
+    let lease = Lease::builder()
+        .ip("fd00::1".parse::<IpAddr>().unwrap())
+        .ends_never()
+        .build()
+        .unwrap();
+    assert!(lease.dates.never_ends);
+
+    assert!(Lease::builder().hostname("laptop").build().is_err());
+    assert!(Lease::builder()
+        .ip(Ipv4Addr::LOCALHOST)
+        .starts(date("2024-05-01T13:00:00Z"))
+        .ends(date("2024-05-01T12:00:00Z"))
+        .build()
+        .is_err());
+    assert!(Lease::builder()
+        .ip(Ipv4Addr::LOCALHOST)
+        .ends(date("2024-05-01T12:00:00Z"))
+        .ends_never()
+        .build()
+        .is_err());
+}
+
+#[test]
+fn lease_with_ip_test() {
+    let lease = Lease::with_ip(Ipv4Addr::new(10, 0, 0, 1));
+    assert_eq!(lease.ip.to_string(), "10.0.0.1");
+    assert!(lease.dates.starts.is_none());
+    assert!(lease.hardware.is_none());
+
+    // leases identical to an empty one are kept
+    let res = parser::parse(
+        "
+    lease 10.0.0.1 {
+    }
+    lease 10.0.0.1 {
+    }",
+    );
+    let leases = res.unwrap().leases;
+    assert_eq!(leases.all().len(), 2);
+    assert_eq!(leases[0], lease);
+}
+
+#[test]
+fn leases_iter_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hostname \"first\";
+    }
+    lease 192.168.0.3 {
+        hostname \"second\";
+    }",
+    );
+    let mut leases = res.unwrap().leases;
+
+    let hostnames: Vec<&str> = leases
+        .iter()
+        .filter_map(|l| l.hostname.as_deref())
+        .collect();
+    assert_eq!(hostnames, vec!["first", "second"]);
+
+    for lease in leases.iter_mut() {
+        lease.abandoned = true;
+    }
+    for lease in &mut leases {
+        lease.hostname = None;
+    }
+    for lease in &leases {
+        assert!(lease.abandoned);
+        assert!(lease.hostname.is_none());
+    }
+
+    let ips: Vec<String> = leases.into_iter().map(|l| l.ip.to_string()).collect();
+    assert_eq!(ips, vec!["192.168.0.2", "192.168.0.3"]);
+}
+
+#[test]
+fn leases_collect_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        abandoned;
+    }
+    lease 192.168.0.3 {
+    }",
+    );
+    let leases = res.unwrap().leases;
+
+    let mut kept: Leases = leases.iter().filter(|l| !l.abandoned).cloned().collect();
+    assert_eq!(kept.all().len(), 1);
+    assert_eq!(kept[0].ip.to_string(), "192.168.0.3");
+
+    kept.extend(vec![
+        Lease::with_ip(Ipv4Addr::new(10, 0, 0, 1)),
+        Lease::with_ip(Ipv4Addr::new(10, 0, 0, 2)),
+    ]);
+    assert_eq!(kept.all().len(), 3);
+    assert_eq!(kept[2].ip.to_string(), "10.0.0.2");
+}
+
+#[test]
+fn leases_accessors_test() {
+    let empty = Leases::new();
+    assert!(empty.is_empty());
+    assert_eq!(empty.len(), 0);
+    assert!(empty.get(0).is_none());
+    assert!(empty.first().is_none());
+    assert!(empty.last().is_none());
+
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+    }
+    lease 192.168.0.3 {
+    }",
+    );
+    let leases = res.unwrap().leases;
+    assert!(!leases.is_empty());
+    assert_eq!(leases.len(), 2);
+    assert_eq!(leases.get(1).unwrap().ip.to_string(), "192.168.0.3");
+    assert!(leases.get(2).is_none());
+    assert_eq!(leases.first().unwrap().ip.to_string(), "192.168.0.2");
+    assert_eq!(leases.last().unwrap().ip.to_string(), "192.168.0.3");
+}
+
+#[test]
+fn latest_per_ip_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        binding state active;
+    }
+    lease 192.168.0.3 {
+        binding state active;
+    }
+    lease 192.168.0.2 {
+        binding state free;
+    }",
+    );
+    let latest = res.unwrap().leases.latest_per_ip();
+
+    assert_eq!(latest.len(), 2);
+    let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+    assert_eq!(
+        latest[&ip("192.168.0.2")].binding_state,
+        Some(BindingState::Free)
+    );
+    assert_eq!(
+        latest[&ip("192.168.0.3")].binding_state,
+        Some(BindingState::Active)
+    );
+}
+
+#[test]
+fn latest_per_mac_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hardware ethernet 00:11:22:33:44:55;
+    }
+    lease 192.168.0.3 {
+        hardware ethernet 0:11:22:33:44:55;
+    }
+    lease 192.168.0.4 {
+        hardware ethernet 66:77:88:99:aa:bb;
+    }
+    lease 192.168.0.5 {
+        binding state free;
+    }",
+    );
+    let latest = res.unwrap().leases.latest_per_mac();
+
+    assert_eq!(latest.len(), 2);
+    let mac = |s: &str| s.parse::<MacAddr>().unwrap();
+    assert_eq!(
+        latest[&mac("00:11:22:33:44:55")].ip.to_string(),
+        "192.168.0.3"
+    );
+    assert_eq!(
+        latest[&mac("66:77:88:99:AA:BB")].ip.to_string(),
+        "192.168.0.4"
+    );
+}
+
+#[test]
+fn active_at_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+        hostname \"first\";
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+        ends never;
+        abandoned;
+    }
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 22:30:00;
+        ends never;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let at = |s: &str| s.parse::<Date>().unwrap();
+
+    let active: Vec<String> = leases
+        .active_at(at("2019-01-01T22:45:00Z"))
+        .map(|l| l.ip.to_string())
+        .collect();
+    assert_eq!(active, vec!["192.168.0.2", "192.168.0.4"]);
+
+    let active: Vec<String> = leases
+        .active_at(at("2019-01-02T00:00:00Z"))
+        .map(|l| l.ip.to_string())
+        .collect();
+    assert_eq!(active, vec!["192.168.0.4"]);
+
+    assert_eq!(
+        leases
+            .active_at(at("2019-01-01T22:45:00Z"))
+            .find(|l| l.hostname.as_deref() == Some("first"))
+            .unwrap()
+            .ip
+            .to_string(),
+        "192.168.0.2"
+    );
+    assert_eq!(leases.active_at(at("2019-01-01T21:00:00Z")).count(), 0);
+}
+
+#[test]
+fn lease_query_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends never;
+        binding state active;
+        hardware ethernet 00:11:22:33:44:55;
+        hostname \"laptop\";
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+        ends never;
+        binding state free;
+        hardware ethernet 00:11:22:33:44:55;
+    }
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 23:00:00;
+        ends never;
+        binding state active;
+        hardware ethernet 0:11:22:33:44:55;
+        client-hostname \"phone\";
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let at = |s: &str| s.parse::<Date>().unwrap();
+
+    let query = leases.query().mac("00:11:22:33:44:55");
+    assert_eq!(query.count(), 3);
+    assert_eq!(query.first().unwrap().ip.to_string(), "192.168.0.2");
+    assert_eq!(query.latest().unwrap().ip.to_string(), "192.168.0.4");
+
+    let lease = leases
+        .query()
+        .mac("00:11:22:33:44:55")
+        .binding(BindingState::Active)
+        .active_at(at("2019-01-01T22:30:00Z"))
+        .latest()
+        .unwrap();
+    assert_eq!(lease.ip.to_string(), "192.168.0.2");
+
+    assert_eq!(leases.query().hostname("laptop").count(), 1);
+    assert_eq!(leases.query().client_hostname("phone").all().len(), 1);
+    assert_eq!(
+        leases
+            .query()
+            .ip("192.168.0.3".parse::<IpAddr>().unwrap())
+            .first()
+            .unwrap()
+            .binding_state,
+        Some(BindingState::Free)
+    );
+    assert_eq!(
+        leases
+            .query()
+            .filter(|l| l.client_hostname.is_some() || l.hostname.is_some())
+            .count(),
+        2
+    );
+    assert_eq!(leases.query().mac("not a mac").count(), 0);
+    assert_eq!(leases.query().count(), 3);
+}
+
+#[test]
+fn lease_index_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        hardware ethernet 00:11:22:33:44:55;
+    }
+    lease 192.168.0.3 {
+        hardware ethernet 0:11:22:33:44:55;
+    }
+    lease 192.168.0.2 {
+        hardware ethernet 66:77:88:99:aa:bb;
+    }
+    lease 192.168.0.4 {
+    }",
+    );
+    let leases = res.unwrap().leases;
+
+    let by_ip = leases.index_by_ip();
+    assert_eq!(by_ip.len(), 3);
+    let first = &by_ip[&"192.168.0.2".parse::<IpAddr>().unwrap()];
+    assert_eq!(first.len(), 2);
+    assert_eq!(
+        first[1].hardware.as_ref().unwrap().mac.as_ref().unwrap(),
+        "66:77:88:99:aa:bb"
+    );
+
+    let by_mac = leases.index_by_mac();
+    assert_eq!(by_mac.len(), 2);
+    let mac = "00:11:22:33:44:55".parse::<MacAddr>().unwrap();
+    let ips: Vec<String> = by_mac[&mac].iter().map(|l| l.ip.to_string()).collect();
+    assert_eq!(ips, vec!["192.168.0.2", "192.168.0.3"]);
+}
+
+#[test]
+fn leases_mutation_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 23:00:00;
+        ends 2 2019/01/01 23:30:00;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 21:00:00;
+        ends never;
+    }
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 22:10:00;
+    }
+    lease 192.168.0.5 {
+        starts 2 2019/01/01 22:00:00;
+        ends never;
+    }",
+    );
+    let mut leases = res.unwrap().leases;
+    let ips =
+        |leases: &Leases| -> Vec<String> { leases.iter().map(|l| l.ip.to_string()).collect() };
+
+    leases.sort_by_key(|l| l.dates.starts);
+    assert_eq!(
+        ips(&leases),
+        vec!["192.168.0.3", "192.168.0.4", "192.168.0.5", "192.168.0.2"]
+    );
+
+    leases.sort_by(|a, b| b.ip.cmp(&a.ip));
+    assert_eq!(
+        ips(&leases),
+        vec!["192.168.0.5", "192.168.0.4", "192.168.0.3", "192.168.0.2"]
+    );
+
+    let now = "2019-01-01T23:00:00Z".parse::<Date>().unwrap();
+    leases.retain(|l| !l.is_expired_at(now));
+    assert_eq!(
+        ips(&leases),
+        vec!["192.168.0.5", "192.168.0.3", "192.168.0.2"]
+    );
+
+    assert_eq!(leases.remove(1).unwrap().ip.to_string(), "192.168.0.3");
+    assert!(leases.remove(2).is_none());
+    assert_eq!(ips(&leases), vec!["192.168.0.5", "192.168.0.2"]);
+}
+
+#[test]
+fn leases_merge_test() {
+    let parse = |s: &str| parser::parse(s).unwrap().leases;
+    let ips =
+        |leases: &Leases| -> Vec<String> { leases.iter().map(|l| l.ip.to_string()).collect() };
+    let rotated = "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 20:00:00;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+    }";
+    let current = "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 21:00:00;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+    }
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 23:00:00;
+    }";
+
+    let mut leases = parse(rotated);
+    leases.merge(parse(current), MergeDuplicates::KeepAll);
+    assert_eq!(
+        ips(&leases),
+        vec![
+            "192.168.0.2",
+            "192.168.0.2",
+            "192.168.0.3",
+            "192.168.0.3",
+            "192.168.0.4"
+        ]
+    );
+    assert_eq!(
+        leases[1].dates.starts.unwrap().to_string(),
+        "2019-01-01T21:00:00Z"
+    );
+
+    let mut leases = parse(rotated);
+    leases.merge(parse(current), MergeDuplicates::Identical);
+    assert_eq!(
+        ips(&leases),
+        vec!["192.168.0.2", "192.168.0.2", "192.168.0.3", "192.168.0.4"]
+    );
+
+    let mut leases = parse(rotated);
+    leases.merge(parse(current), MergeDuplicates::LatestPerIp);
+    assert_eq!(
+        ips(&leases),
+        vec!["192.168.0.2", "192.168.0.3", "192.168.0.4"]
+    );
+    assert_eq!(
+        leases[0].dates.starts.unwrap().to_string(),
+        "2019-01-01T21:00:00Z"
+    );
+
+    let mut leases = Leases::new();
+    leases.merge(parse(current), MergeDuplicates::KeepAll);
+    assert_eq!(leases.len(), 3);
+}
+
+#[test]
+fn leases_diff_test() {
+    let before = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:02;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 20:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:03;
+    }
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 20:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:04;
+    }
+    lease 192.168.0.5 {
+        starts 2 2019/01/01 20:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:05;
+    }",
+    )
+    .unwrap()
+    .leases;
+    let after = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 20:30:00;
+        ends 2 2019/01/01 21:30:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:02;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 20:00:00;
+        binding state free;
+        hardware ethernet 00:00:00:00:00:03;
+    }
+    lease 192.168.0.5 {
+        starts 2 2019/01/01 20:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:05;
+    }
+    lease 192.168.0.4 {
+        starts 2 2019/01/01 22:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:44;
+    }
+    lease 192.168.0.6 {
+        starts 2 2019/01/01 22:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:06;
+    }",
+    )
+    .unwrap()
+    .leases;
+    let ips =
+        |leases: &[&Lease]| -> Vec<String> { leases.iter().map(|l| l.ip.to_string()).collect() };
+
+    let diff = before.diff(&after);
+    assert_eq!(ips(&diff.added), vec!["192.168.0.4", "192.168.0.6"]);
+    assert_eq!(ips(&diff.removed), vec!["192.168.0.4"]);
+    assert_eq!(diff.renewed.len(), 1);
+    assert_eq!(diff.renewed[0].after.ip.to_string(), "192.168.0.2");
+    assert_eq!(
+        diff.renewed[0].after.dates.ends.unwrap().to_string(),
+        "2019-01-01T21:30:00Z"
+    );
+    assert_eq!(diff.state_changed.len(), 1);
+    assert_eq!(
+        diff.state_changed[0].before.binding_state,
+        Some(BindingState::Active)
+    );
+    assert_eq!(
+        diff.state_changed[0].after.binding_state,
+        Some(BindingState::Free)
+    );
+
+    assert!(after.diff(&after).is_empty());
+}
+
+#[test]
+fn group_by_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        binding state active;
+        hardware ethernet 00:11:22:00:00:01;
+    }
+    lease 192.168.0.3 {
+        binding state free;
+        hardware ethernet 00:11:22:00:00:02;
+    }
+    lease 192.168.0.4 {
+        binding state active;
+        hardware ethernet 66:77:88:00:00:01;
+    }",
+    );
+    let leases = res.unwrap().leases;
+
+    let by_state = leases.group_by(|l| l.binding_state.clone());
+    assert_eq!(by_state.len(), 2);
+    assert_eq!(by_state[&Some(BindingState::Active)].len(), 2);
+    assert_eq!(
+        by_state[&Some(BindingState::Free)][0].ip.to_string(),
+        "192.168.0.3"
+    );
+
+    // vendor prefix of the hardware address
+    let by_vendor = leases.group_by(|l| {
+        l.hardware
+            .as_ref()
+            .and_then(|h| h.mac.as_ref())
+            .map(|m| m.as_bytes()[..3].to_vec())
+    });
+    let ips: Vec<String> = by_vendor[&Some(vec![0x00, 0x11, 0x22])]
+        .iter()
+        .map(|l| l.ip.to_string())
+        .collect();
+    assert_eq!(ips, vec!["192.168.0.2", "192.168.0.3"]);
+}
+
+#[test]
+fn summary_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:02;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 19:00:00;
+        ends 2 2019/01/01 22:00:00;
+        binding state free;
+        hardware ethernet 00:00:00:00:00:03;
+    }
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 21:00:00;
+        ends never;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:02;
+    }
+    lease 192.168.0.4 {
+        abandoned;
+    }",
+    );
+    let summary = res.unwrap().leases.summary();
+
+    assert_eq!(summary.total, 4);
+    assert_eq!(summary.by_binding_state[&BindingState::Active], 2);
+    assert_eq!(summary.by_binding_state[&BindingState::Free], 1);
+    assert_eq!(summary.without_binding_state, 1);
+    assert_eq!(summary.abandoned, 1);
+    assert_eq!(summary.unique_macs, 2);
+    assert_eq!(summary.unique_ips, 3);
+    assert_eq!(
+        summary.earliest_start.unwrap().to_string(),
+        "2019-01-01T19:00:00Z"
+    );
+    assert_eq!(
+        summary.latest_end.unwrap().to_string(),
+        "2019-01-01T22:00:00Z"
+    );
+    assert_eq!(
+        summary.average_duration,
+        Some(std::time::Duration::from_secs(2 * 3600))
+    );
+
+    let empty = Leases::new().summary();
+    assert_eq!(empty.total, 0);
+    assert!(empty.earliest_start.is_none());
+    assert!(empty.average_duration.is_none());
+}
+
+#[test]
+fn utilization_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 21:00:00;
+        ends 2 2019/01/01 21:00:00;
+        binding state free;
+    }
+    lease 10.0.0.12 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 20:30:00;
+        binding state active;
+    }
+    lease 10.0.0.13 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        abandoned;
+    }
+    lease 10.0.1.5 {
+        starts 2 2019/01/01 20:00:00;
+        ends never;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let ranges = [
+        "10.0.0.10-10.0.0.19".parse::<Range>().unwrap(),
+        "10.0.1.0/24".parse::<Range>().unwrap(),
+    ];
+
+    let usage = leases.utilization(&ranges, "2019-01-01T22:00:00Z".parse::<Date>().unwrap());
+    assert_eq!(usage.ranges.len(), 2);
+
+    let first = &usage.ranges[0];
+    assert_eq!(first.size, 10);
+    assert_eq!(first.used, 1);
+    assert_eq!(first.abandoned, 1);
+    assert_eq!(first.free, 8);
+    assert!((first.ratio() - 0.1).abs() < f64::EPSILON);
+
+    let second = &usage.ranges[1];
+    assert_eq!(second.range.start.to_string(), "10.0.1.0");
+    assert_eq!(second.range.end.to_string(), "10.0.1.255");
+    assert_eq!(second.size, 256);
+    assert_eq!(second.used, 1);
+
+    assert_eq!(usage.size, 266);
+    assert_eq!(usage.used, 2);
+    assert_eq!(usage.abandoned, 1);
+    assert_eq!(usage.free, 263);
+
+    assert!("10.0.0.0/33".parse::<Range>().is_err());
+    assert!("10.0.0.1".parse::<Range>().is_err());
+    assert_eq!("0.0.0.0/0".parse::<Range>().unwrap().size(), 1 << 32);
+}
+
+#[test]
+fn conflicts_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:01;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:02;
+    }
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/02 00:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:03;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 21:30:00;
+        ends 2 2019/01/01 22:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:04;
+    }
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:30:00;
+        ends 2 2019/01/01 23:30:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:01;
+    }
+    lease 10.0.0.12 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        uid \"client-a\";
+    }
+    lease 10.0.0.12 {
+        starts 2 2019/01/01 21:00:00;
+        ends 2 2019/01/01 22:00:00;
+        uid \"client-b\";
+    }
+    lease 10.0.0.13 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state free;
+        hardware ethernet 00:00:00:00:00:05;
+    }
+    lease 10.0.0.13 {
+        starts 2 2019/01/01 21:00:00;
+        ends 2 2019/01/01 22:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:06;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let conflicts = leases.conflicts();
+
+    assert_eq!(conflicts.len(), 2);
+    assert_eq!(conflicts[0].ip.to_string(), "10.0.0.10");
+    let macs: Vec<String> = conflicts[0]
+        .leases
+        .iter()
+        .map(|l| {
+            l.hardware
+                .as_ref()
+                .unwrap()
+                .mac
+                .as_ref()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(
+        macs,
+        vec![
+            "00:00:00:00:00:01",
+            "00:00:00:00:00:03",
+            "00:00:00:00:00:01"
+        ]
+    );
+    assert_eq!(conflicts[1].ip.to_string(), "10.0.0.12");
+    assert_eq!(conflicts[1].leases.len(), 2);
+}
+
+#[test]
+fn expiring_within_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 22:30:00;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 22:45:00;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 21:45:00;
+        ends 2 2019/01/02 01:45:00;
+    }
+    lease 10.0.0.12 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+    }
+    lease 10.0.0.13 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:59:00;
+    }
+    lease 10.0.0.14 {
+        starts 2 2019/01/01 20:00:00;
+        ends never;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let now = "2019-01-01T22:00:00Z".parse::<Date>().unwrap();
+
+    let ips: Vec<String> = leases
+        .expiring_within(now, std::time::Duration::from_secs(3600))
+        .iter()
+        .map(|l| l.ip.to_string())
+        .collect();
+    assert_eq!(ips, vec!["10.0.0.10", "10.0.0.12"]);
+
+    assert!(leases
+        .expiring_within(now, std::time::Duration::from_secs(60))
+        .is_empty());
+}
+
+#[test]
+fn expired_before_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+    }
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 21:00:00;
+        ends 2 2019/01/01 21:30:00;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 21:00:00;
+        ends 2 2019/01/01 23:00:00;
+    }
+    lease 10.0.0.12 {
+        starts 2 2019/01/01 20:00:00;
+        ends never;
+    }",
+    );
+    let mut leases = res.unwrap().leases;
+    let cutoff = "2019-01-01T22:00:00Z".parse::<Date>().unwrap();
+
+    let expired = leases.expired_before(cutoff);
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].ip.to_string(), "10.0.0.10");
+    assert_eq!(
+        expired[0].dates.ends.unwrap().to_string(),
+        "2019-01-01T21:30:00Z"
+    );
+
+    let removed = leases.remove_expired_before(cutoff);
+    assert_eq!(removed.len(), 2);
+    assert!(removed.iter().all(|l| l.ip.to_string() == "10.0.0.10"));
+    let ips: Vec<String> = leases.iter().map(|l| l.ip.to_string()).collect();
+    assert_eq!(ips, vec!["10.0.0.11", "10.0.0.11", "10.0.0.12"]);
+}
+
+#[test]
+fn occupancy_series_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 20:30:00;
+        ends 2 2019/01/01 22:00:00;
+    }
+    lease 10.0.0.12 {
+        starts 2 2019/01/01 21:00:00;
+        ends never;
+    }
+    lease 10.0.0.13 {
+        starts 2 2019/01/01 20:00:00;
+        ends never;
+        abandoned;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let at = |s: &str| s.parse::<Date>().unwrap();
+    let half_hour = std::time::Duration::from_secs(1800);
+
+    let series = leases.occupancy_series(
+        at("2019-01-01T19:30:00Z"),
+        at("2019-01-01T22:30:00Z"),
+        half_hour,
+    );
+    let counts: Vec<usize> = series.iter().map(|(_, c)| *c).collect();
+    assert_eq!(counts, vec![0, 1, 2, 3, 2, 2, 1]);
+    assert_eq!(series[3].0.to_string(), "2019-01-01T21:00:00Z");
+
+    for (date, count) in &series {
+        assert_eq!(leases.active_at(*date).count(), *count);
+    }
+
+    let single = leases.occupancy_series(
+        at("2019-01-01T21:00:00Z"),
+        at("2019-01-01T22:00:00Z"),
+        std::time::Duration::from_secs(0),
+    );
+    assert_eq!(single.len(), 1);
+    assert!(leases
+        .occupancy_series(
+            at("2019-01-02T00:00:00Z"),
+            at("2019-01-01T00:00:00Z"),
+            half_hour
+        )
+        .is_empty());
+}
+
+#[test]
+fn lease_hash_test() {
+    let block = "
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+        hardware ethernet 00:00:00:00:00:01;
+    }
+    lease 10.0.0.11 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 21:00:00;
+    }";
+    let res = parser::parse(format!(
+        "{}{}
+    lease 10.0.0.10 {{
+        starts 2 2019/01/01 21:00:00;
+        ends 2 2019/01/01 22:00:00;
+        hardware ethernet 00:00:00:00:00:01;
+    }}",
+        block, block
+    ));
+    let leases = res.unwrap().leases;
+    assert_eq!(leases.len(), 5);
+
+    let unique: std::collections::HashSet<&Lease> = leases.iter().collect();
+    assert_eq!(unique.len(), 3);
+
+    let mut seen = std::collections::HashSet::new();
+    let deduplicated: Leases = leases
+        .into_iter()
+        .filter(|l| seen.insert(l.clone()))
+        .collect();
+    let ips: Vec<String> = deduplicated.iter().map(|l| l.ip.to_string()).collect();
+    assert_eq!(ips, vec!["10.0.0.10", "10.0.0.11", "10.0.0.10"]);
+}
+
+#[test]
+fn sort_by_field_test() {
+    let res = parser::parse(
+        "
+    lease 10.0.0.9 {
+        starts 2 2019/01/01 21:00:00;
+        ends never;
+        binding state free;
+        hardware ethernet 00:00:00:00:00:0b;
+    }
+    lease 10.0.0.10 {
+        starts 2 2019/01/01 20:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+        hardware ethernet 00:00:00:00:00:0a;
+        uid 1:2;
+    }
+    lease 10.0.0.2 {
+        ends 2 2019/01/01 22:00:00;
+        uid 1:1;
+    }",
+    );
+    let mut leases = res.unwrap().leases;
+    let ips =
+        |leases: &Leases| -> Vec<String> { leases.iter().map(|l| l.ip.to_string()).collect() };
+
+    leases.sort_by_field(LeasesField::LeasedIP, SortOrder::Ascending);
+    assert_eq!(ips(&leases), vec!["10.0.0.2", "10.0.0.9", "10.0.0.10"]);
+
+    leases.sort_by_field(LeasesField::LeasedIP, SortOrder::Descending);
+    assert_eq!(ips(&leases), vec!["10.0.0.10", "10.0.0.9", "10.0.0.2"]);
+
+    leases.sort_by_field(LeasesField::Starts, SortOrder::Ascending);
+    assert_eq!(ips(&leases), vec!["10.0.0.10", "10.0.0.9", "10.0.0.2"]);
+
+    leases.sort_by_field(LeasesField::Starts, SortOrder::Descending);
+    assert_eq!(ips(&leases), vec!["10.0.0.9", "10.0.0.10", "10.0.0.2"]);
+
+    leases.sort_by_field(LeasesField::Ends, SortOrder::Ascending);
+    assert_eq!(ips(&leases), vec!["10.0.0.2", "10.0.0.10", "10.0.0.9"]);
+
+    leases.sort_by_field(LeasesField::MAC, SortOrder::Ascending);
+    assert_eq!(ips(&leases), vec!["10.0.0.10", "10.0.0.9", "10.0.0.2"]);
+
+    leases.sort_by_field(LeasesField::BindingState, SortOrder::Descending);
+    assert_eq!(ips(&leases), vec!["10.0.0.9", "10.0.0.10", "10.0.0.2"]);
+
+    leases.sort_by_field(LeasesField::Uid, SortOrder::Ascending);
+    assert_eq!(ips(&leases), vec!["10.0.0.2", "10.0.0.10", "10.0.0.9"]);
+}
+
+#[test]
+fn parse_file_test() {
+    let path = std::env::temp_dir().join(format!("dhcpd-parser-{}.leases", std::process::id()));
+    let mut content =
+        b"\xef\xbb\xbflease 192.168.0.2 {\n  client-hostname \"caf\xe9\";\n}\n".to_vec();
+    content.extend_from_slice(b"lease 192.168.0.3 {\n}\n");
+    std::fs::write(&path, content).unwrap();
+
+    let res = parser::parse_file(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let leases = res.unwrap().leases;
+    assert_eq!(leases.len(), 2);
+    assert_eq!(leases[0].ip.to_string(), "192.168.0.2");
+    assert_eq!(leases[0].client_hostname.as_deref(), Some("caf\u{fffd}"));
+
+    let err = parser::parse_file(&path).unwrap_err();
+    assert!(matches!(err, ParseError::Io { .. }));
+    assert!(err.to_string().starts_with("Cannot read "));
+    // the size limit applies to the bytes of the file, before invalid UTF-8
+    // is replaced
+    let content = b"lease 192.168.0.2 {\n  client-hostname \"\xe9\xe9\xe9\";\n}\n";
+    std::fs::write(&path, content).unwrap();
+    let with_max_size = |max| ParserOptions {
+        limits: Limits {
+            max_input_size: Some(max),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let res = parser::parse_file_with_options(&path, &with_max_size(content.len()));
+    let err = parser::parse_file_with_options(&path, &with_max_size(content.len() - 1));
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(res.is_ok());
+    assert!(matches!(err.unwrap_err(), ParseError::LimitExceeded { .. }));
+}
+
+#[test]
+fn sorted_by_start_test() {
+    let res = parser::parse(
+        "lease 10.0.0.1 {
+        starts 3 2020/01/08 00:00:00;
+    }
+    lease 10.0.0.2 {
+        starts 1 2020/01/06 00:00:00;
+    }
+    lease 10.0.0.3 {
+    }
+    lease 10.0.0.4 {
+        starts 3 2020/01/08 00:00:00;
+    }
+    lease 10.0.0.5 {
+        starts 5 2020/01/10 00:00:00;
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let sorted = leases.sorted_by_start();
+    let ips =
+        |leases: &[&Lease]| -> Vec<String> { leases.iter().map(|l| l.ip.to_string()).collect() };
+    let date = |s: &str| -> Date { s.parse().unwrap() };
+
+    assert_eq!(sorted.len(), 4);
+    assert_eq!(
+        ips(&sorted.iter().collect::<Vec<_>>()),
+        vec!["10.0.0.2", "10.0.0.1", "10.0.0.4", "10.0.0.5"]
+    );
+
+    assert_eq!(
+        ips(sorted
+            .leases_starting_between(date("2020-01-08T00:00:00Z"), date("2020-01-10T00:00:00Z"))),
+        vec!["10.0.0.1", "10.0.0.4", "10.0.0.5"]
+    );
+    assert_eq!(
+        ips(sorted
+            .leases_starting_between(date("2020-01-01T00:00:00Z"), date("2020-01-07T00:00:00Z"))),
+        vec!["10.0.0.2"]
+    );
+    assert!(sorted
+        .leases_starting_between(date("2020-01-11T00:00:00Z"), date("2020-02-01T00:00:00Z"))
+        .is_empty());
+    assert!(sorted
+        .leases_starting_between(date("2020-01-10T00:00:00Z"), date("2020-01-06T00:00:00Z"))
+        .is_empty());
+}
+
+#[test]
+fn filter_by_test() {
+    let res = parser::parse(
+        "lease 10.0.0.1 {
+        hardware ethernet aa:aa:aa:aa:aa:aa;
+        client-hostname \"first\";
+    }
+    lease 10.0.0.2 {
+        hardware ethernet bb:bb:bb:bb:bb:bb;
+    }
+    lease 10.0.0.1 {
+        hardware ethernet aa:aa:aa:aa:aa:aa;
+        client-hostname \"second\";
+    }",
+    );
+    let leases = res.unwrap().leases;
+    let mac: MacAddr = "aa:aa:aa:aa:aa:aa".parse().unwrap();
+    let has_mac = |l: &Lease| l.hardware.as_ref().and_then(|h| h.mac.as_ref()) == Some(&mac);
+
+    let filtered = leases.filter_by(has_mac);
+    assert_eq!(filtered.len(), 2);
+    assert_eq!(filtered[0].client_hostname.as_deref(), Some("first"));
+    assert_eq!(filtered[1].client_hostname.as_deref(), Some("second"));
+
+    let last = leases.find_last_by(has_mac).unwrap();
+    assert_eq!(last.client_hostname.as_deref(), Some("second"));
+
+    assert!(leases.filter_by(|l| l.abandoned).is_empty());
+    assert!(leases.find_last_by(|l| l.abandoned).is_none());
+}
+
+#[test]
+fn truncated_input_test() {
+    let content = "# The format of this file is documented in the dhcpd.leases(5) manual page.
+# This lease file was written by isc-dhcp-4.4.1
+
+server-duid \"\\000\\001\\000\\001\";
+
+lease 192.168.0.2 {
+  starts 2 2019/01/01 22:00:00 UTC;
+  ends never;
+  tstp epoch 1546380000;
+  binding state active;
+  next binding state free;
+  rewind binding state free;
+  hardware ethernet 11:11:11:11:11:11;
+  uid \"\\001\\021\\021\\021\\021\\021\\021\";
+  set vendor-class-identifier = \"MSFT 5.0\";
+  option agent.circuit-id 0:1:2:3;
+  billing subclass \"vendor\" \"MSFT\";
+  on expiry or release {
+    set ddns-fwd-name = \"host.example.com\";
+  }
+  client-hostname \"CLIENT\";
+  hostname \"HOST\";
+  abandoned;
+}
+
+host client {
+  dynamic;
+  hardware ethernet 22:22:22:22:22:22;
+  fixed-address 192.168.0.3;
+}
+
+ia-na \"\\001\\000\\000\\000\" {
+  cltt 2 2019/01/01 22:00:00;
+  iaaddr 2001:db8::1 {
+    binding state active;
+    preferred-life 3600;
+    max-life 7200;
+    ends 3 2019/01/02 00:00:00;
+  }
+}
+
+class \"vendor\" {
+  match option vendor-class-identifier;
+}
+subclass \"vendor\" \"MSFT\";
+
+group \"staff\" {
+  default-lease-time 3600;
+}
+";
+    parser::parse(content).unwrap();
+
+    for (i, _) in content.char_indices() {
+        // must return, whatever the outcome
+        let _ = parser::parse(&content[..i]);
+    }
+
+    let res = parser::parse("lease 192.168.0.2 {\n  starts 2 2019/01/01");
+    assert!(res.is_err());
+    let res = parser::parse("lease 192.168.0.2 {\n  uid");
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .starts_with("Semicolon expected, got end of input"));
+    let res = parser::parse("lease 192.168.0.2 {\n  hostname \"HOST\"");
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .starts_with("Semicolon expected, got end of input"));
+    let res = parser::parse("lease 192.168.0.2 {\n  starts 2 2019/aa/01 22:00:00;\n}");
+    assert!(res.is_err());
+}
+
+#[test]
+fn error_location_test() {
+    let res = parser::parse(
+        "lease 192.168.0.2 {
+  starts 2 2019/01/01 22:00:00;
+  hardware ethernet 11:11:11:11:11:11 12;
+}",
+    );
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Expected semicolon, found 12 at line 3, column 39"
+    );
+
+    let res = parser::parse("lease 192.168.0.2 {\n  binding state active;\n");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Expected end of section with '}', got 'None' at line 3, column 1"
+    );
+
+    let res = parser::parse("lease 192.168.0.2 {\n  hostname \"café\" x;\n}");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Expected semicolon, found x at line 2, column 19"
+    );
+
+    let res = parser::parse("\n\nlease 192.168.0.2 {\n  hostname \"HOST;\n}");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Unterminated string \"HOST;\n} at line 4, column 12"
+    );
+}
+
+#[test]
+fn parse_error_test() {
+    let err = parser::parse("lease 192.168.0.2 {\n  hostname \"HOST\" x;\n}").unwrap_err();
+    assert!(matches!(err, ParseError::UnexpectedToken { .. }));
+    assert_eq!(err.message(), "Expected semicolon, found x");
+    let span = err.span().unwrap();
+    assert_eq!((span.line, span.column), (2, 19));
+    assert_eq!((span.start, span.end), (38, 39));
+
+    let err = parser::parse("lease 192.168.0.2 {\n  hardware ethernet").unwrap_err();
+    assert!(matches!(err, ParseError::UnexpectedEof { .. }));
+
+    let err = parser::parse("lease 192.168.0.2 {\n  abandoned;\n").unwrap_err();
+    assert!(matches!(err, ParseError::UnterminatedBlock { .. }));
+
+    let err = parser::parse("lease 192.168.0.2 {\n  hostname \"HOST;\n}").unwrap_err();
+    assert!(matches!(err, ParseError::UnterminatedBlock { .. }));
+
+    let err = parser::parse("lease 192.168.0.2 {\n  starts 2 2019/01/01 25:00:00;\n}").unwrap_err();
+    assert!(matches!(err, ParseError::InvalidDate { .. }));
+    assert_eq!(err.span().unwrap().line, 2);
+
+    let err = parser::parse("lease 192.168.0.2 {\n  hardware ethernet zz;\n}").unwrap_err();
+    assert!(matches!(err, ParseError::InvalidValue { .. }));
+
+    fn parse_boxed() -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(parser::parse("lease 192.168.0.2 {\n  abandoned\n}")?
+            .leases
+            .len())
+    }
+    assert!(parse_boxed().is_err());
+}
+
+#[test]
+fn lenient_mode_test() {
+    let input = "
+    failover-state \"peer\" {
+        my state normal at 2 2019/01/01 22:00:00;
+    }
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        new-statement foo { bar; baz; }
+        hardware ethernet 11:11:11:11:11:11;
+    }
+    future-option 42;
+    lease 192.168.0.3 {
+        abandoned;
+    }";
+
+    assert!(parser::parse(input).is_err());
+
+    let options = ParserOptions {
+        mode: ParseMode::Lenient,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(res.leases.len(), 2);
+    assert!(res.leases[0].hardware.is_some());
+    assert!(res.leases[0].unknown_statements.is_empty());
+    assert!(res.leases[1].abandoned);
+
+    let messages: Vec<&str> = res.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Skipped unknown declaration 'failover-state \"peer\" { my state normal at 2 2019/01/01 22:00:00; }'",
+            "Skipped unknown statement 'new-statement foo { bar; baz; }' in lease 192.168.0.2",
+            "Skipped unknown declaration 'future-option 42'",
+        ]
+    );
+
+    // statements kept in passthrough are not reported
+    let options = ParserOptions {
+        mode: ParseMode::Lenient,
+        passthrough_unknown: true,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    assert_eq!(res.diagnostics.len(), 2);
+    assert_eq!(res.leases[0].unknown_statements.len(), 1);
+}
+
+#[test]
+fn utc_offset_test() {
+    let input = "
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 3 2019/01/02 01:00:00 UTC;
+        tstp epoch 1546380000;
+    }
+    ia-na \"\\001\\000\\000\\000\" {
+        cltt 2 2019/01/01 22:00:00;
+    }";
+    let options = ParserOptions {
+        utc_offset: 2 * 3600,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    let lease = &res.leases[0];
+
+    assert_eq!(
+        lease.dates.starts.unwrap().to_string(),
+        "2019-01-01T20:00:00Z"
+    );
+    assert_eq!(
+        lease.dates.ends.unwrap().to_string(),
+        "2019-01-02T01:00:00Z"
+    );
+    assert_eq!(lease.tstp.unwrap().to_string(), "2019-01-01T22:00:00Z");
+    assert_eq!(res.ias[0].cltt.unwrap().to_string(), "2019-01-01T20:00:00Z");
+
+    let res = parser::parse(input).unwrap();
+    assert_eq!(
+        res.leases[0].dates.starts.unwrap().to_string(),
+        "2019-01-01T22:00:00Z"
+    );
+    // offsets pushing a date out of range are an error, not an overflow
+    for (input, utc_offset) in &[
+        (
+            "lease 1.2.3.4 { starts 3 99999999999999/01/01 00:00:00; }",
+            3600,
+        ),
+        ("lease 1.2.3.4 { starts 2 2019/01/01 22:00:00; }", i64::MIN),
+    ] {
+        let options = ParserOptions {
+            utc_offset: *utc_offset,
+            ..Default::default()
+        };
+        let err = parser::parse_with_options(*input, &options).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDate { .. }));
+    }
+}
+
+#[test]
+fn time_zone_test() {
+    let res = parser::parse(
+        "
+    lease 192.168.0.2 {
+        starts 3 2020/01/01 02:00:00 +0200;
+        ends 3 2020/01/01 00:00:00 -05:30;
+        tstp 3 2020/01/01 00:00:00 GMT;
+    }",
+    )
+    .unwrap();
+    let lease = &res.leases[0];
+    assert_eq!(
+        lease.dates.starts.unwrap().to_string(),
+        "2020-01-01T00:00:00Z"
+    );
+    assert_eq!(
+        lease.dates.ends.unwrap().to_string(),
+        "2020-01-01T05:30:00Z"
+    );
+    assert_eq!(lease.tstp.unwrap().to_string(), "2020-01-01T00:00:00Z");
+
+    // an explicit time zone takes precedence over utc_offset
+    let options = ParserOptions {
+        utc_offset: 3600,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(
+        "lease 1.2.3.4 { ends 4 2020/01/01 00:00:00 +0200; }",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        res.leases[0].dates.ends.unwrap().to_string(),
+        "2019-12-31T22:00:00Z"
+    );
+
+    for input in &[
+        "lease 1.2.3.4 { ends 4 2020/01/01 00:00:00 CEST; }",
+        "lease 1.2.3.4 { ends 4 2020/01/01 00:00:00 +25; }",
+    ] {
+        let err = parser::parse(*input).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDate { .. }));
+    }
+}
+
+#[test]
+fn diagnostics_test() {
+    let input = "lease 192.168.0.2 {
+  starts 3 2019/01/01 22:00:00;
+  ends 2 2019/02/30 22:00:00;
+}
+lease 192.168.0.3 {
+  starts 2 2019/01/01 22:00:00;
+  ends 1 2018/12/31 22:00:00;
+  hardware ethernet 11:11:11:11:11:11;
+  hardware ethernet 22:22:22:22:22:22;
+}
+lease 192.168.0.4 {
+  starts 2 2019/01/01 22:00:00;
+  ends never;
+}";
+
+    let res = parser::parse(input).unwrap();
+    assert_eq!(res.leases.len(), 3);
+
+    let found: Vec<(Severity, usize, &str)> = res
+        .diagnostics
+        .iter()
+        .map(|d| (d.severity, d.span.unwrap().line, d.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                Severity::Info,
+                2,
+                "Weekday 3 of 2019-01-01T22:00:00Z in lease 192.168.0.2 should be 2"
+            ),
+            (
+                Severity::Warning,
+                3,
+                "2019-02-30T22:00:00Z in lease 192.168.0.2 does not exist, read as 2019-03-02T22:00:00Z"
+            ),
+            (
+                Severity::Warning,
+                9,
+                "'hardware' repeated in lease 192.168.0.3"
+            ),
+            (
+                Severity::Warning,
+                7,
+                "Lease 192.168.0.3 ends before it starts"
+            ),
+        ]
+    );
+    assert_eq!(
+        res.diagnostics[2].to_string(),
+        "warning: 'hardware' repeated in lease 192.168.0.3 at line 9, column 3"
+    );
+}
+
+#[test]
+fn recover_test() {
+    let input = "lease 192.168.0.2 {
+  starts 2 2019/01/01 22:00:00;
+}
+lease 192.168.0.3 {
+  starts 2 2019/01/01 22:00:00;
+  hardware ethernet zz:11:11:11:11:11;
+  uid \"\\001\";
+}
+lease 192.168.0.300 {
+  abandoned;
+}
+lease 192.168.0.4 {
+  starts 2 2019/01/01 22:00:00
+lease 192.168.0.5 {
+  abandoned;
+}";
+
+    assert!(parser::parse(input).is_err());
+
+    let options = ParserOptions {
+        recover: true,
+        ..Default::default()
+    };
+    let res = parser::parse_with_options(input, &options).unwrap();
+    let ips: Vec<String> = res.leases.iter().map(|l| l.ip.to_string()).collect();
+    assert_eq!(ips, vec!["192.168.0.2", "192.168.0.5"]);
+
+    let found: Vec<(Severity, usize)> = res
+        .diagnostics
+        .iter()
+        .map(|d| (d.severity, d.span.unwrap().line))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (Severity::Error, 6),
+            (Severity::Error, 10),
+            (Severity::Error, 14)
+        ]
+    );
+    assert!(res.diagnostics[1]
+        .message
+        .contains("'192.168.0.300' is not a valid lease address"));
+}
+
+#[test]
+fn limits_test() {
+    let input = "lease 192.168.0.2 {
+  starts 2 2019/01/01 22:00:00;
+  on commit { if a { set b = c; } }
+}
+lease 192.168.0.3 {
+  abandoned;
+}";
+    let with_limits = |limits: Limits| ParserOptions {
+        limits,
+        ..Default::default()
+    };
+
+    let all = Limits {
+        max_input_size: Some(input.len()),
+        max_tokens: Some(100),
+        max_leases: Some(2),
+        max_depth: Some(3),
+    };
+    let res = parser::parse_with_options(input, &with_limits(all)).unwrap();
+    assert_eq!(res.leases.len(), 2);
+
+    let cases = vec![
+        (
+            Limits {
+                max_input_size: Some(input.len() - 1),
+                ..Default::default()
+            },
+            "Input of 124 bytes is over the limit of 123 bytes",
+        ),
+        (
+            Limits {
+                max_tokens: Some(10),
+                ..Default::default()
+            },
+            "Too many tokens in the input at line 3, column 13",
+        ),
+        (
+            Limits {
+                max_leases: Some(1),
+                ..Default::default()
+            },
+            "More than 1 leases in the input at line 5, column 1",
+        ),
+        (
+            Limits {
+                max_depth: Some(2),
+                ..Default::default()
+            },
+            "Blocks nested too deeply at line 3, column 20",
+        ),
+    ];
+    for (limits, expected) in cases {
+        let err = parser::parse_with_options(input, &with_limits(limits)).unwrap_err();
+        assert!(matches!(err, ParseError::LimitExceeded { .. }));
+        assert_eq!(err.to_string(), expected);
+    }
+
+    // limits are not recovered from
+    let options = ParserOptions {
+        recover: true,
+        ..with_limits(Limits {
+            max_leases: Some(1),
+            ..Default::default()
+        })
+    };
+    assert!(parser::parse_with_options(input, &options).is_err());
+}
+
+#[test]
+fn out_of_range_dates_test() {
+    for input in &[
+        "lease 1.2.3.4 { starts 3 99999999999999/01/01 00:00:00; }",
+        "lease 1.2.3.4 { starts 3 2020/01/99999999999999999 00:00:00; }",
+    ] {
+        let err = parser::parse(*input).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDate { .. }));
+    }
+
+    let res = parser::parse("lease 1.2.3.4 { starts epoch -9223372036854775808; }").unwrap();
+    assert_eq!(res.diagnostics.len(), 1);
+    assert_eq!(res.diagnostics[0].severity, Severity::Warning);
+    assert!(res.diagnostics[0].message.ends_with("is out of range"));
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "oui")]
+extern crate dhcpd_parser;
+
+use std::path::Path;
+
+use crate::dhcpd_parser::common::MacAddr;
+use crate::dhcpd_parser::error::ParseError;
+use crate::dhcpd_parser::leases::Hardware;
+use crate::dhcpd_parser::oui::OuiDatabase;
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::parser::LeasesMethods;
+
+const REGISTRY: &str = "OUI/MA-L                                                    Organization
+company_id                                                  Organization
+                                                            Address
+
+00-00-0C   (hex)\t\tCisco Systems, Inc
+00000C     (base 16)\t\tCisco Systems, Inc
+\t\t\t\t170 WEST TASMAN DRIVE
+\t\t\t\tSan Jose  CA  95134-1706
+\t\t\t\tUS
+
+B8-27-EB   (hex)\t\tRaspberry Pi Foundation
+B827EB     (base 16)\t\tRaspberry Pi Foundation
+\t\t\t\tMitchell Wood House
+\t\t\t\tCaldecote  Cambridgeshire  CB23 7NU
+\t\t\t\tGB
+";
+
+#[test]
+fn oui_database_test() {
+    let db = REGISTRY.parse::<OuiDatabase>().unwrap();
+    assert_eq!(db.len(), 2);
+
+    let mac = |s: &str| s.parse::<MacAddr>().unwrap();
+    assert_eq!(
+        db.lookup(&mac("00:00:0c:12:34:56")),
+        Some("Cisco Systems, Inc")
+    );
+    assert_eq!(
+        db.lookup(&mac("b8:27:eb:00:00:01")),
+        Some("Raspberry Pi Foundation")
+    );
+    assert_eq!(db.lookup(&mac("00:11:22:33:44:55")), None);
+    assert_eq!(db.lookup(&mac("00:00")), None);
+
+    let hw = "ethernet b8:27:eb:00:00:01".parse::<Hardware>().unwrap();
+    assert_eq!(hw.vendor(&db), Some("Raspberry Pi Foundation"));
+    assert_eq!("ethernet".parse::<Hardware>().unwrap().vendor(&db), None);
+
+    let path = std::env::temp_dir().join(format!("dhcpd-parser-{}.oui", std::process::id()));
+    std::fs::write(&path, REGISTRY).unwrap();
+    let res = OuiDatabase::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(res.unwrap(), db);
+
+    let err = OuiDatabase::load("/nonexistent/oui.txt").unwrap_err();
+    assert!(
+        matches!(err, ParseError::Io { ref path, .. } if path == Path::new("/nonexistent/oui.txt"))
+    );
+}
+
+#[test]
+fn group_by_vendor_test() {
+    let mut db = OuiDatabase::new();
+    db.insert([0xb8, 0x27, 0xeb], "Raspberry Pi Foundation");
+
+    let res = parser::parse(
+        "
+    lease 10.0.0.10 {
+        hardware ethernet b8:27:eb:00:00:01;
+    }
+    lease 10.0.0.11 {
+        hardware ethernet b8:27:eb:00:00:02;
+    }
+    lease 10.0.0.12 {
+        hardware ethernet 00:11:22:33:44:55;
+    }
+    lease 10.0.0.13 {
+    }",
+    );
+    let leases = res.unwrap().leases;
+
+    let by_vendor = leases.group_by_vendor(&db);
+    assert_eq!(by_vendor.len(), 2);
+    assert_eq!(by_vendor[&Some("Raspberry Pi Foundation")].len(), 2);
+    assert_eq!(by_vendor[&None].len(), 2);
+}